@@ -0,0 +1,52 @@
+use crate::{Error, Result};
+use arrow::array::{Array, ArrayRef, AsArray};
+use arrow::datatypes::{ArrowPrimitiveType, DataType};
+
+/// Whether values of type `from` can be converted to `to` without losing precision.
+///
+/// Conversions between integer types, and from integers to decimals, are allowed too. They are
+/// checked for each value, see [`coerce_primitive`].
+pub(crate) fn can_coerce(from: &DataType, to: &DataType) -> bool {
+    use DataType::*;
+    match (from, to) {
+        (from, to) if from == to => true,
+        (from, to) if from.is_integer() && to.is_integer() => true,
+        (from, Decimal128(_, _) | Decimal256(_, _)) if from.is_integer() => true,
+        (Int8 | Int16 | UInt8 | UInt16, Float32) => true,
+        (Int8 | Int16 | Int32 | UInt8 | UInt16 | UInt32 | Float32, Float64) => true,
+        (Utf8, LargeUtf8) | (LargeUtf8, Utf8) => true,
+        (Binary, LargeBinary) | (LargeBinary, Binary) => true,
+        _ => false,
+    }
+}
+
+/// Converts `array` to `data_type`, returning an error for each value that doesn't fit.
+pub(crate) fn coerce_primitive<T: ArrowPrimitiveType>(
+    array: &ArrayRef,
+    data_type: &DataType,
+) -> Result<impl Iterator<Item = Result<Option<T::Native>>> + 'static> {
+    if !can_coerce(array.data_type(), data_type) {
+        return Err(Error::unexpected_type(
+            format!("{data_type:?} or a type that can be coerced to it"),
+            array.data_type(),
+        ));
+    }
+    let from = array.data_type().clone();
+    // Values which don't fit become null
+    let coerced = arrow::compute::cast(array, data_type)?;
+    let coerced = coerced.as_primitive::<T>().clone();
+    let nulls = array.logical_nulls();
+    Ok((0..coerced.len()).map(move |row| {
+        if coerced.is_valid(row) {
+            Ok(Some(coerced.value(row)))
+        } else if nulls.as_ref().is_some_and(|nulls| nulls.is_null(row)) {
+            Ok(None)
+        } else {
+            Err(Error::Overflow {
+                row,
+                from: from.clone(),
+                to: coerced.data_type().clone(),
+            })
+        }
+    }))
+}
@@ -0,0 +1,63 @@
+//! Reading CSV files with structs, behind the `csv` feature.
+use crate::schema::read_schema;
+use crate::{batch_rows, FromStructArray, Result};
+use arrow::csv::reader::Format;
+use arrow::csv::ReaderBuilder;
+use arrow::datatypes::Schema;
+use arrow::error::ArrowError;
+use std::io::{BufRead, BufReader, Read, Seek};
+use std::sync::Arc;
+
+/// The number of records [`read_inferred`] infers the column types from
+const INFER_RECORDS: usize = 1000;
+
+/// Reads the rows of a CSV file with a header as `T`, parsing the columns as the types `T` reads
+/// them as.
+///
+/// Columns are found in the header like in any struct array, i.e. by name (or alias, or position
+/// with `by_position`). Columns `T` doesn't read, or reads with a custom decoder, are parsed as
+/// strings.
+pub fn read<T>(reader: impl Read) -> Result<impl Iterator<Item = Result<T>>>
+where
+    T: for<'a> FromStructArray<'a>,
+{
+    let mut reader = BufReader::new(reader);
+    let mut header = String::new();
+    reader.read_line(&mut header).map_err(ArrowError::from)?;
+    let (header, _) = format().infer_schema(header.as_bytes(), Some(0))?;
+    // The header was read above
+    rows(read_schema::<T>(header.fields(), false), reader, false)
+}
+
+/// Reads the rows of a CSV file with a header as `T`, with column types inferred from the first
+/// 1000 records (e.g. `Int64` or `Utf8`, see `#[arrow_struct(coerce)]` for narrower types).
+///
+/// The reader is rewound after inferring the types.
+pub fn read_inferred<T>(reader: impl Read + Seek) -> Result<impl Iterator<Item = Result<T>>>
+where
+    T: for<'a> FromStructArray<'a>,
+{
+    let mut reader = BufReader::new(reader);
+    let (schema, _) = format().infer_schema(&mut reader, Some(INFER_RECORDS))?;
+    reader.rewind().map_err(ArrowError::from)?;
+    rows(schema, reader, true)
+}
+
+fn format() -> Format {
+    Format::default().with_header(true)
+}
+
+fn rows<T>(
+    schema: Schema,
+    reader: impl BufRead,
+    header: bool,
+) -> Result<impl Iterator<Item = Result<T>>>
+where
+    T: for<'a> FromStructArray<'a>,
+{
+    T::validate_fields(schema.fields())?;
+    let reader = ReaderBuilder::new(Arc::new(schema))
+        .with_header(header)
+        .build_buffered(reader)?;
+    Ok(reader.flat_map(batch_rows))
+}
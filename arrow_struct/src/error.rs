@@ -0,0 +1,171 @@
+use arrow::datatypes::DataType;
+use arrow::error::ArrowError;
+use std::fmt::{Display, Formatter};
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Errors reported by [`FromArrayRef::try_from_array_ref`](crate::FromArrayRef::try_from_array_ref).
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The array did not have the data type the target type can be read from.
+    UnexpectedType { expected: String, actual: DataType },
+    /// A struct array is missing columns required by the target type, or has columns the target
+    /// type does not allow (see `#[arrow_struct(deny_unknown_columns)]`).
+    Columns {
+        missing: Vec<String>,
+        unknown: Vec<String>,
+    },
+    /// More than one column matches the name of a column the target type is read from, e.g. with
+    /// `#[arrow_struct(match_case = "insensitive")]`.
+    AmbiguousColumn { name: String, matches: Vec<String> },
+    /// A null was found where the target type requires a value.
+    UnexpectedNull { row: usize },
+    /// A value could not be coerced to the target type without overflowing.
+    Overflow {
+        row: usize,
+        from: DataType,
+        to: DataType,
+    },
+    /// An error from arrow.
+    Arrow(ArrowError),
+    /// An error from parquet.
+    #[cfg(feature = "parquet")]
+    Parquet(parquet::errors::ParquetError),
+    /// A value could not be converted to the field type, e.g. with `#[arrow_struct(try_from = "...")]`.
+    Conversion { row: usize, message: String },
+    /// An error from a custom decoder, e.g. of `#[arrow_struct(with = "...")]`.
+    Custom(String),
+    /// An error that occurred while reading the column with the given name.
+    Column { name: String, source: Box<Error> },
+}
+
+impl Error {
+    pub fn unexpected_type(expected: impl Into<String>, actual: &DataType) -> Self {
+        Error::UnexpectedType {
+            expected: expected.into(),
+            actual: actual.clone(),
+        }
+    }
+
+    pub fn missing_column(name: impl Into<String>) -> Self {
+        Error::Columns {
+            missing: vec![name.into()],
+            unknown: vec![],
+        }
+    }
+
+    pub fn conversion(row: usize, error: impl Display) -> Self {
+        Error::Conversion {
+            row,
+            message: error.to_string(),
+        }
+    }
+
+    pub fn custom(message: impl Display) -> Self {
+        Error::Custom(message.to_string())
+    }
+
+    /// Replaces the row the error occurred at, e.g. the position of a list value with the row of
+    /// the list.
+    pub(crate) fn at_row(self, row: usize) -> Self {
+        match self {
+            Error::UnexpectedNull { .. } => Error::UnexpectedNull { row },
+            Error::Overflow { from, to, .. } => Error::Overflow { row, from, to },
+            Error::Conversion { message, .. } => Error::Conversion { row, message },
+            Error::Column { name, source } => Error::Column {
+                name,
+                source: Box::new(source.at_row(row)),
+            },
+            error => error,
+        }
+    }
+
+    /// Adds the name of the column the error occurred in.
+    pub fn in_column(self, name: impl Into<String>) -> Self {
+        Error::Column {
+            name: name.into(),
+            source: Box::new(self),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnexpectedType { expected, actual } => {
+                write!(f, "Expected {expected}, was {actual:?}")
+            }
+            Error::Columns { missing, unknown } => {
+                if !missing.is_empty() {
+                    write!(f, "missing columns: {}", missing.join(", "))?;
+                }
+                if !missing.is_empty() && !unknown.is_empty() {
+                    write!(f, "; ")?;
+                }
+                if !unknown.is_empty() {
+                    write!(f, "unknown columns: {}", unknown.join(", "))?;
+                }
+                Ok(())
+            }
+            Error::AmbiguousColumn { name, matches } => {
+                write!(
+                    f,
+                    "{name} matches more than one column: {}",
+                    matches.join(", ")
+                )
+            }
+            Error::UnexpectedNull { row } => write!(f, "unexpected null at row {row}"),
+            Error::Overflow { row, from, to } => {
+                write!(
+                    f,
+                    "value at row {row} does not fit into {to:?} (from {from:?})"
+                )
+            }
+            Error::Arrow(error) => write!(f, "{error}"),
+            #[cfg(feature = "parquet")]
+            Error::Parquet(error) => write!(f, "{error}"),
+            Error::Conversion { row, message } => {
+                write!(f, "could not convert value at row {row}: {message}")
+            }
+            Error::Custom(message) => write!(f, "{message}"),
+            Error::Column { name, source } => write!(f, "column {name}: {source}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Column { source, .. } => Some(source.as_ref()),
+            Error::Arrow(error) => Some(error),
+            #[cfg(feature = "parquet")]
+            Error::Parquet(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<ArrowError> for Error {
+    fn from(error: ArrowError) -> Self {
+        match error {
+            // E.g. from a Parquet row filter
+            ArrowError::ExternalError(error) if error.is::<Error>() => {
+                *error.downcast::<Error>().unwrap()
+            }
+            error => Error::Arrow(error),
+        }
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl From<parquet::errors::ParquetError> for Error {
+    fn from(error: parquet::errors::ParquetError) -> Self {
+        match error {
+            parquet::errors::ParquetError::External(error) if error.is::<ArrowError>() => {
+                Error::from(*error.downcast::<ArrowError>().unwrap())
+            }
+            error => Error::Parquet(error),
+        }
+    }
+}
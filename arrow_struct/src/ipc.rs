@@ -0,0 +1,337 @@
+//! Reading Arrow IPC files and streams into structs.
+use crate::__private::Either;
+use crate::{batch_rows, FromStructArray, Result, ToStructArray};
+use arrow::array::{Array, StructArray};
+use arrow::buffer::Buffer;
+use arrow::datatypes::{Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::ipc::convert::fb_to_schema;
+use arrow::ipc::reader::{read_footer_length, FileDecoder};
+use arrow::ipc::root_as_footer;
+use arrow::ipc::writer::{FileWriter, StreamWriter};
+use arrow::record_batch::RecordBatch;
+use std::io::{BufReader, Read, Seek, Write};
+use std::iter::FlatMap;
+use std::sync::Arc;
+
+type Rows<I, T> =
+    FlatMap<I, Vec<Result<T>>, fn(std::result::Result<RecordBatch, ArrowError>) -> Vec<Result<T>>>;
+
+/// Reads the rows of an Arrow IPC file as `T`.
+///
+/// The schema of the file is checked when it's opened. Like for Parquet, the rows of every batch
+/// are read up front, so `T` can't borrow from the arrays.
+pub struct FileReader<T, R: Read + Seek> {
+    schema: SchemaRef,
+    rows: Rows<arrow::ipc::reader::FileReader<R>, T>,
+}
+
+impl<T, R: Read + Seek> FileReader<T, R>
+where
+    T: for<'a> FromStructArray<'a>,
+{
+    pub fn try_new(reader: R) -> Result<Self> {
+        let reader = arrow::ipc::reader::FileReader::try_new(reader, None)?;
+        let schema = reader.schema();
+        T::validate_fields(schema.fields())?;
+        Ok(FileReader {
+            schema,
+            rows: reader.flat_map(batch_rows),
+        })
+    }
+
+    /// The schema of the file
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+impl<T, R: Read + Seek> Iterator for FileReader<T, R>
+where
+    T: for<'a> FromStructArray<'a>,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.rows.next()
+    }
+}
+
+/// Reads the rows of an Arrow IPC stream as `T`, like [`FileReader`].
+pub struct StreamReader<T, R: Read> {
+    schema: SchemaRef,
+    rows: Rows<arrow::ipc::reader::StreamReader<BufReader<R>>, T>,
+}
+
+impl<T, R: Read> StreamReader<T, R>
+where
+    T: for<'a> FromStructArray<'a>,
+{
+    /// Reads the schema from the stream, which is buffered.
+    pub fn try_new(reader: R) -> Result<Self> {
+        let reader = arrow::ipc::reader::StreamReader::try_new_buffered(reader, None)?;
+        let schema = reader.schema();
+        T::validate_fields(schema.fields())?;
+        Ok(StreamReader {
+            schema,
+            rows: reader.flat_map(batch_rows),
+        })
+    }
+
+    /// The schema of the stream
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+impl<T, R: Read> Iterator for StreamReader<T, R>
+where
+    T: for<'a> FromStructArray<'a>,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.rows.next()
+    }
+}
+
+/// An Arrow IPC file in memory, whose rows can be borrowed, e.g. as `&str`.
+///
+/// With the `mmap` feature, [`MmapFile::open`] maps the file into memory, so large files can be
+/// read without copying them (as long as their buffers are aligned, which they are when written
+/// by arrow).
+pub struct MmapFile {
+    schema: SchemaRef,
+    batches: Vec<StructArray>,
+}
+
+impl MmapFile {
+    /// Maps the file at `path` into memory and decodes its batches.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified while it's mapped, see [`memmap2::Mmap`].
+    #[cfg(feature = "mmap")]
+    pub unsafe fn open(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let file = std::fs::File::open(path).map_err(ArrowError::from)?;
+        let mmap = memmap2::Mmap::map(&file).map_err(ArrowError::from)?;
+        let data = std::ptr::NonNull::new(mmap.as_ptr() as *mut u8).expect("mmap is not null");
+        // The buffer keeps the mapping alive
+        let buffer = Buffer::from_custom_allocation(data, mmap.len(), Arc::new(mmap));
+        Self::from_buffer(buffer)
+    }
+
+    /// Decodes the batches of the Arrow IPC file in `buffer`, without copying their data.
+    pub fn from_buffer(buffer: Buffer) -> Result<Self> {
+        let invalid = |message: &str| ArrowError::IpcError(format!("invalid IPC file: {message}"));
+        if buffer.len() < 10 {
+            return Err(invalid("too short").into());
+        }
+        let trailer_start = buffer.len() - 10;
+        let footer_len = read_footer_length(buffer[trailer_start..].try_into().unwrap())?;
+        let footer = trailer_start
+            .checked_sub(footer_len)
+            .and_then(|footer_start| root_as_footer(&buffer[footer_start..trailer_start]).ok())
+            .ok_or_else(|| invalid("unable to read the footer"))?;
+        let schema = Arc::new(fb_to_schema(
+            footer.schema().ok_or_else(|| invalid("missing schema"))?,
+        ));
+
+        let mut decoder = FileDecoder::new(schema.clone(), footer.version());
+        // Blocks outside of the buffer, e.g. of a truncated file, would panic when sliced
+        let block_data = |block: &arrow::ipc::Block| {
+            let offset = usize::try_from(block.offset()).ok()?;
+            let len = usize::try_from(block.bodyLength())
+                .ok()?
+                .checked_add(usize::try_from(block.metaDataLength()).ok()?)?;
+            (offset.checked_add(len)? <= buffer.len())
+                .then(|| buffer.slice_with_length(offset, len))
+        };
+        let block_data = |block| block_data(block).ok_or_else(|| invalid("block out of bounds"));
+        for block in footer.dictionaries().iter().flatten() {
+            decoder.read_dictionary(block, &block_data(block)?)?;
+        }
+        let batches = footer
+            .recordBatches()
+            .iter()
+            .flatten()
+            .filter_map(|block| {
+                block_data(block)
+                    .and_then(|data| decoder.read_record_batch(block, &data))
+                    .transpose()
+            })
+            .map(|batch| Ok(StructArray::from(batch?)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(MmapFile { schema, batches })
+    }
+
+    /// The schema of the file
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.batches.iter().map(Array::len).sum()
+    }
+
+    /// Reads the rows of all batches as `T`, which can borrow from the file.
+    ///
+    /// The schema is checked against `T` before reading any rows.
+    pub fn rows<'a, T: FromStructArray<'a> + 'a>(
+        &'a self,
+    ) -> Result<impl Iterator<Item = Result<T>> + 'a> {
+        T::validate_fields(self.schema.fields())?;
+        Ok(self
+            .batches
+            .iter()
+            .flat_map(|batch| match T::try_from_columns(batch) {
+                Ok(rows) => Either::Left(rows),
+                Err(error) => Either::Right(std::iter::once(Err(error))),
+            }))
+    }
+}
+
+/// The most rows [`BatchWriter`] buffers before converting them to arrays
+const CHUNK_SIZE: usize = 1024;
+
+/// Writes rows of `T` to an Arrow IPC file or stream, one at a time.
+///
+/// The rows are converted to arrays in small chunks, which are written as one batch when it has
+/// [`max_rows`](Self::with_max_rows) rows, or its arrays take up [`max_bytes`](Self::with_max_bytes).
+///
+/// [`ToStructArray`] only converts slices of rows, so there are no array builders to append rows
+/// to. Instead, a chunk only buffers as many rows as are expected to fit in the rest of the batch,
+/// going by the size of the rows converted before, so batches go over `max_bytes` by about one
+/// row. Chunks are concatenated into the batch when it's written, which copies them once.
+pub struct BatchWriter<T, W: Write> {
+    writer: IpcWriter<W>,
+    schema: SchemaRef,
+    rows: Vec<T>,
+    chunks: Vec<RecordBatch>,
+    /// The number of rows and the memory size of `chunks`
+    chunk_rows: usize,
+    chunk_bytes: usize,
+    /// The memory size per row of the last chunk, if any was converted yet
+    row_bytes: Option<usize>,
+    max_rows: usize,
+    max_bytes: usize,
+}
+
+enum IpcWriter<W: Write> {
+    File(FileWriter<W>),
+    Stream(StreamWriter<W>),
+}
+
+impl<T: ToStructArray, W: Write> BatchWriter<T, W> {
+    /// Writes an Arrow IPC file, which is only complete after [`BatchWriter::finish`].
+    pub fn try_new_file(writer: W) -> Result<Self> {
+        let schema = Arc::new(Schema::new(T::fields()));
+        let writer = IpcWriter::File(FileWriter::try_new(writer, &schema)?);
+        Ok(Self::new(writer, schema))
+    }
+
+    /// Writes an Arrow IPC stream, where every batch can be read as soon as it's flushed.
+    pub fn try_new_stream(writer: W) -> Result<Self> {
+        let schema = Arc::new(Schema::new(T::fields()));
+        let writer = IpcWriter::Stream(StreamWriter::try_new(writer, &schema)?);
+        Ok(Self::new(writer, schema))
+    }
+
+    fn new(writer: IpcWriter<W>, schema: SchemaRef) -> Self {
+        BatchWriter {
+            writer,
+            schema,
+            rows: Vec::new(),
+            chunks: Vec::new(),
+            chunk_rows: 0,
+            chunk_bytes: 0,
+            row_bytes: None,
+            max_rows: 64 * 1024,
+            max_bytes: 64 * 1024 * 1024,
+        }
+    }
+
+    /// Sets the number of rows after which a batch is written (64Ki by default).
+    ///
+    /// If more rows are already buffered, they're written with the next row.
+    pub fn with_max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = max_rows.max(1);
+        self
+    }
+
+    /// Sets the memory size of arrays after which a batch is written (64MiB by default).
+    ///
+    /// Batches only go over it by more than a row if rows get larger than those written before.
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Buffers the row, and writes a batch if a threshold is reached.
+    pub fn write(&mut self, row: T) -> Result<()> {
+        self.rows.push(row);
+        if self.rows.len() >= self.chunk_size() {
+            self.convert_rows();
+            if self.chunk_rows >= self.max_rows || self.chunk_bytes >= self.max_bytes {
+                self.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The number of rows to convert at once: at most 1024, the rows left in the batch, and the
+    /// rows expected to fit in its bytes left. The first row is converted alone to measure it.
+    fn chunk_size(&self) -> usize {
+        let rows_left = self.max_rows.saturating_sub(self.chunk_rows);
+        let rows_fitting = match self.row_bytes {
+            Some(row_bytes) => self.max_bytes.saturating_sub(self.chunk_bytes) / row_bytes.max(1),
+            None => 1,
+        };
+        CHUNK_SIZE.min(rows_left).min(rows_fitting).max(1)
+    }
+
+    fn convert_rows(&mut self) {
+        if self.rows.is_empty() {
+            return;
+        }
+        let chunk = T::to_record_batch(&self.rows);
+        self.rows.clear();
+        let bytes = chunk.get_array_memory_size();
+        self.row_bytes = Some(bytes / chunk.num_rows());
+        self.chunk_rows += chunk.num_rows();
+        self.chunk_bytes += bytes;
+        self.chunks.push(chunk);
+    }
+
+    /// Writes the buffered rows as a batch.
+    pub fn flush(&mut self) -> Result<()> {
+        self.convert_rows();
+        if self.chunks.is_empty() {
+            return Ok(());
+        }
+        let batch = match self.chunks.as_slice() {
+            [chunk] => chunk.clone(),
+            chunks => arrow::compute::concat_batches(&self.schema, chunks)?,
+        };
+        self.chunks.clear();
+        self.chunk_rows = 0;
+        self.chunk_bytes = 0;
+        match &mut self.writer {
+            IpcWriter::File(writer) => writer.write(&batch)?,
+            IpcWriter::Stream(writer) => {
+                writer.write(&batch)?;
+                writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the remaining rows and finishes the file or stream.
+    pub fn finish(mut self) -> Result<W> {
+        self.flush()?;
+        Ok(match self.writer {
+            IpcWriter::File(writer) => writer.into_inner()?,
+            IpcWriter::Stream(writer) => writer.into_inner()?,
+        })
+    }
+}
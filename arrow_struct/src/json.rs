@@ -0,0 +1,63 @@
+//! Reading newline-delimited JSON with structs, behind the `json` feature.
+use crate::schema::read_schema;
+use crate::{batch_rows, FromStructArray, Result};
+use arrow::datatypes::Schema;
+use arrow::error::ArrowError;
+use arrow::json::reader::{infer_json_schema, infer_json_schema_from_seekable};
+use arrow::json::ReaderBuilder;
+use std::io::{BufRead, BufReader, Cursor, Read, Seek};
+use std::sync::Arc;
+
+/// The number of records the keys (and for [`read_inferred`], the schema) are inferred from
+const INFER_RECORDS: usize = 1000;
+
+/// Reads newline-delimited JSON objects as `T`, parsing the values as the types `T` reads them as.
+///
+/// The keys are taken from the first 1000 objects, and found like the columns of any struct array,
+/// i.e. by name (or alias, or position in the order they first appear with `by_position`). Keys
+/// `T` doesn't read, or reads with a custom decoder, keep their inferred types, and missing keys
+/// are null.
+pub fn read<T>(reader: impl Read) -> Result<impl Iterator<Item = Result<T>>>
+where
+    T: for<'a> FromStructArray<'a>,
+{
+    let mut reader = BufReader::new(reader);
+    let mut sample = Vec::new();
+    for _ in 0..INFER_RECORDS {
+        if reader
+            .read_until(b'\n', &mut sample)
+            .map_err(ArrowError::from)?
+            == 0
+        {
+            break;
+        }
+    }
+    let (inferred, _) = infer_json_schema(sample.as_slice(), None)?;
+    // The sample is read again, followed by the rest
+    rows(
+        read_schema::<T>(inferred.fields(), true),
+        Cursor::new(sample).chain(reader),
+    )
+}
+
+/// Reads newline-delimited JSON objects as `T`, with a schema inferred from the first 1000
+/// objects (e.g. `Int64` for numbers, see `#[arrow_struct(coerce)]` for narrower types).
+///
+/// The reader is rewound after inferring the schema.
+pub fn read_inferred<T>(reader: impl Read + Seek) -> Result<impl Iterator<Item = Result<T>>>
+where
+    T: for<'a> FromStructArray<'a>,
+{
+    let mut reader = BufReader::new(reader);
+    let (schema, _) = infer_json_schema_from_seekable(&mut reader, Some(INFER_RECORDS))?;
+    rows(schema, reader)
+}
+
+fn rows<T>(schema: Schema, reader: impl BufRead) -> Result<impl Iterator<Item = Result<T>>>
+where
+    T: for<'a> FromStructArray<'a>,
+{
+    T::validate_fields(schema.fields())?;
+    let reader = ReaderBuilder::new(Arc::new(schema)).build(reader)?;
+    Ok(reader.flat_map(batch_rows))
+}
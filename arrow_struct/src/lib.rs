@@ -1,42 +1,428 @@
+use __private::Either;
 pub use arrow::array::Array;
 pub use arrow::array::ArrayRef;
 pub use arrow::array::AsArray;
-use arrow::array::{GenericListArray, OffsetSizeTrait};
+pub use arrow::array::StructArray;
+use arrow::array::{GenericListArray, OffsetSizeTrait, UInt64Array};
+pub use arrow::datatypes::Fields;
 pub use arrow::datatypes::Int32Type;
 pub use arrow::datatypes::Int64Type;
 use arrow::datatypes::{
-    DataType, Float32Type, Float64Type, Int16Type, Int8Type, UInt16Type, UInt32Type, UInt64Type,
-    UInt8Type,
+    ArrowPrimitiveType, DataType, Decimal128Type, Field, Float32Type, Float64Type, Int16Type,
+    Int8Type, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
 };
 pub use arrow::record_batch::RecordBatch;
 pub use bytes::Bytes;
+use std::borrow::Cow;
 use std::fmt::Debug;
+use std::sync::Arc;
 
-pub use arrow_struct_derive::Deserialize;
+pub use arrow_struct_derive::{Deserialize, Serialize};
+
+mod coerce;
+#[cfg(feature = "csv")]
+pub mod csv;
+mod error;
+pub mod ipc;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+#[cfg(any(feature = "csv", feature = "json"))]
+mod schema;
+mod serialize;
+#[cfg(feature = "async")]
+pub mod stream;
+pub use error::{Error, Result};
+pub use serialize::{ColumnOptions, ToArrayRef, ToStructArray};
 
 pub trait FromArrayRef<'a>: Sized {
-    fn from_array_ref(array: &'a ArrayRef) -> impl Iterator<Item = Self>;
+    /// Whether the iterators can return fewer rows than the array has, like a struct with
+    /// `#[arrow_struct(skip_null_rows)]`. Such types can only be read at the outermost level, as
+    /// nested values are matched up with their rows by position.
+    const SKIPS_ROWS: bool = false;
+
+    /// How `Self` is read, see [`Column::column_type`].
+    fn column_type() -> ColumnType {
+        ColumnType::Unknown
+    }
+
+    fn from_array_ref(array: &'a ArrayRef) -> impl Iterator<Item = Self> + Send;
+
+    /// Like [`FromArrayRef::from_array_ref`], but returns errors instead of panicking.
+    ///
+    /// Problems with the array as a whole (e.g. an unexpected data type) are returned up front,
+    /// problems with individual rows (e.g. an unexpected null) are returned by the iterator.
+    fn try_from_array_ref(
+        array: &'a ArrayRef,
+    ) -> Result<impl Iterator<Item = Result<Self>> + Send> {
+        Ok(Self::from_array_ref(array).map(Ok))
+    }
+
+    /// Like [`FromArrayRef::try_from_array_ref`], but also accepts arrays whose values can be
+    /// converted to `Self` without losing precision, e.g. an `Int32` array for `i64`.
+    ///
+    /// Narrowing integer conversions are accepted too, and return an error for each value that
+    /// doesn't fit. This is what `#[arrow_struct(coerce)]` uses.
+    fn coerce_from_array_ref(
+        array: &'a ArrayRef,
+    ) -> Result<impl Iterator<Item = Result<Self>> + Send> {
+        Self::try_from_array_ref(array)
+    }
+}
+
+/// Reading owned types from an array the iterator takes, so it can be returned or sent to another
+/// thread. Implemented for every type that doesn't borrow from the array.
+pub trait FromArrayOwned: for<'a> FromArrayRef<'a> + Send + 'static {
+    /// Like [`FromArrayRef::from_array_ref`], but keeps the array alive in the iterator.
+    ///
+    /// The array is read in slices of 1024 rows, whose values are read up front.
+    fn from_array_owned(array: ArrayRef) -> impl Iterator<Item = Self> + Send + 'static {
+        let len = array.len();
+        let copy_chunks = has_lists(array.data_type());
+        (0..len).step_by(OWNED_CHUNK_SIZE).flat_map(move |offset| {
+            let chunk_len = OWNED_CHUNK_SIZE.min(len - offset);
+            // Lists of a slice skip over the values of all rows before it, so to not do that for
+            // every chunk, chunks with lists are copied instead
+            let chunk = if copy_chunks {
+                let indices =
+                    UInt64Array::from_iter_values(offset as u64..(offset + chunk_len) as u64);
+                arrow::compute::take(&array, &indices, None).expect("indices are in bounds")
+            } else {
+                array.slice(offset, chunk_len)
+            };
+            Self::from_array_ref(&chunk).collect::<Vec<_>>()
+        })
+    }
+}
+
+/// Whether arrays of `data_type` have lists, which are read from the start of their values
+fn has_lists(data_type: &DataType) -> bool {
+    match data_type {
+        DataType::List(_) | DataType::LargeList(_) => true,
+        DataType::Struct(fields) => fields.iter().any(|field| has_lists(field.data_type())),
+        _ => false,
+    }
+}
+
+impl<T: for<'a> FromArrayRef<'a> + Send + 'static> FromArrayOwned for T {}
+
+/// The number of rows [`FromArrayOwned::from_array_owned`] reads at once
+const OWNED_CHUNK_SIZE: usize = 1024;
+
+/// Rust types with a native Arrow data type, used when a column has to be converted to it,
+/// e.g. for `#[arrow_struct(cast)]`.
+pub trait ArrowField {
+    fn data_type() -> DataType;
+
+    /// Whether `Self` can represent null, i.e., is an `Option`
+    const NULLABLE: bool = false;
+}
+
+impl<T: ArrowField> ArrowField for Option<T> {
+    fn data_type() -> DataType {
+        T::data_type()
+    }
+
+    const NULLABLE: bool = true;
+}
+
+/// A column read by a [`FromStructArray`] type.
+#[derive(Debug, Clone, Copy)]
+pub struct Column {
+    /// The name of the column, followed by its aliases
+    pub names: &'static [&'static str],
+    /// The position of the column, if it's read by position instead of by name
+    /// (`#[arrow_struct(index = ...)]`). Then the only name is the position, like `#3`.
+    pub index: Option<usize>,
+    /// Whether the name is compared as is, without normalizing it, like the names in a
+    /// `#[arrow_struct(path = ...)]`
+    pub exact_name: bool,
+    /// Whether reading fails if the column is missing
+    pub required: bool,
+    /// How the column is read. A function, as the columns of nested structs are only listed when
+    /// they're needed.
+    pub column_type: fn() -> ColumnType,
+}
+
+// Function pointers can't be compared reliably, so columns are equal if they read the same column
+impl PartialEq for Column {
+    fn eq(&self, other: &Self) -> bool {
+        self.names == other.names
+            && self.index == other.index
+            && self.exact_name == other.exact_name
+            && self.required == other.required
+    }
+}
+
+impl Eq for Column {}
+
+impl Column {
+    pub fn name(&self) -> &'static str {
+        self.names[0]
+    }
+
+    /// Whether the column is read from the field at `position` of a struct.
+    fn reads(&self, position: usize, field: &Field, normalize: fn(&str) -> Cow<'_, str>) -> bool {
+        match self.index {
+            Some(index) => index == position,
+            None if self.exact_name => self.names.contains(&field.name().as_str()),
+            None => {
+                let name = normalize(field.name());
+                self.names.iter().any(|column| normalize(column) == name)
+            }
+        }
+    }
+}
+
+/// How a [`Column`] is read, so readers can decode only what's needed.
+#[derive(Debug, Clone)]
+pub enum ColumnType {
+    /// Read by a custom decoder (`#[arrow_struct(with = ...)]`), which may need anything
+    Unknown,
+    /// Read from an array of this data type
+    Native(DataType),
+    /// Read from a struct, of which only `columns` are needed
+    Struct {
+        columns: Vec<Column>,
+        normalize: fn(&str) -> Cow<'_, str>,
+        deny_unknown_columns: bool,
+    },
+    /// Read from a list whose values are read as the inner type
+    List(Box<ColumnType>),
+}
+
+impl ColumnType {
+    /// A struct read as `T`
+    pub fn of_struct<'a, T: FromStructArray<'a>>() -> Self {
+        ColumnType::Struct {
+            columns: T::columns(),
+            normalize: T::normalize_name,
+            deny_unknown_columns: T::DENY_UNKNOWN_COLUMNS,
+        }
+    }
+
+    /// A struct of which `columns` are read by their exact names, like the parents of a
+    /// `#[arrow_struct(path = ...)]` field
+    pub fn struct_of(columns: Vec<Column>) -> Self {
+        ColumnType::Struct {
+            columns,
+            normalize: |name| Cow::Borrowed(name),
+            deny_unknown_columns: false,
+        }
+    }
+}
+
+/// Reads `Self` from the columns of a [`StructArray`]. This is what `#[derive(Deserialize)]` implements.
+///
+/// Every `FromStructArray` type is also [`FromArrayRef`], and so is `Option` of it, where a null
+/// struct becomes `None`.
+pub trait FromStructArray<'a>: Sized {
+    /// Whether columns not read by `Self` are an error.
+    const DENY_UNKNOWN_COLUMNS: bool = false;
+
+    /// Whether rows with nulls are dropped (see [`FromArrayRef::SKIPS_ROWS`]).
+    const SKIPS_ROWS: bool = false;
+
+    /// The columns `Self` is read from.
+    fn columns() -> Vec<Column>;
+
+    /// Normalizes the name of a column before it's compared to the names of [`columns`](Self::columns)
+    /// (see `#[arrow_struct(match_case = "insensitive")]` and `#[arrow_struct(normalize = "...")]`).
+    fn normalize_name(name: &str) -> Cow<'_, str> {
+        Cow::Borrowed(name)
+    }
+
+    /// Reads `Self` from the columns of `array`, ignoring all other columns.
+    ///
+    /// This lets `#[arrow_struct(flatten)]` read a struct from the columns of its parent.
+    fn try_from_columns(
+        array: &'a StructArray,
+    ) -> Result<impl Iterator<Item = Result<Self>> + Send>;
+
+    fn try_from_struct_array(
+        array: &'a StructArray,
+    ) -> Result<impl Iterator<Item = Result<Self>> + Send> {
+        Self::validate_fields(array.fields())?;
+        Self::try_from_columns(array)
+    }
+
+    /// Checks that a struct with the given fields has the columns needed to read `Self`.
+    fn validate_fields(fields: &Fields) -> Result<()> {
+        let columns = Self::columns();
+        let missing = columns
+            .iter()
+            .filter(|column| {
+                column.required
+                    && !fields.iter().enumerate().any(|(position, field)| {
+                        column.reads(position, field, Self::normalize_name)
+                    })
+            })
+            .map(|column| column.name().to_string())
+            .collect::<Vec<_>>();
+        let unknown = if Self::DENY_UNKNOWN_COLUMNS {
+            fields
+                .iter()
+                .enumerate()
+                .filter(|(position, field)| {
+                    !columns
+                        .iter()
+                        .any(|column| column.reads(*position, field, Self::normalize_name))
+                })
+                .map(|(_, field)| field.name().clone())
+                .collect()
+        } else {
+            vec![]
+        };
+        if missing.is_empty() && unknown.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Columns { missing, unknown })
+        }
+    }
+}
+
+fn unwrap_rows<T>(rows: Result<impl Iterator<Item = Result<T>>>) -> impl Iterator<Item = T> {
+    rows.unwrap_or_else(|error| panic!("{error}"))
+        .map(|row| row.unwrap_or_else(|error| panic!("{error}")))
+}
+
+fn as_struct(array: &ArrayRef) -> Result<&StructArray> {
+    array
+        .as_struct_opt()
+        .ok_or_else(|| Error::unexpected_type("Struct", array.data_type()))
+}
+
+/// Reads the rows of a batch up front, for readers that only yield owned batches.
+///
+/// The readers check the schema with [`FromStructArray::validate_fields`] once, before the first batch.
+fn batch_rows<T, E>(batch: std::result::Result<RecordBatch, E>) -> Vec<Result<T>>
+where
+    T: for<'a> FromStructArray<'a>,
+    E: Into<Error>,
+{
+    let array = match batch {
+        Ok(batch) => StructArray::from(batch),
+        Err(error) => return vec![Err(error.into())],
+    };
+    let rows = match T::try_from_columns(&array) {
+        Ok(rows) => rows.collect(),
+        Err(error) => vec![Err(error)],
+    };
+    rows
+}
+
+impl<'a, T: FromStructArray<'a>> FromArrayRef<'a> for T {
+    const SKIPS_ROWS: bool = <T as FromStructArray<'a>>::SKIPS_ROWS;
+
+    fn column_type() -> ColumnType {
+        ColumnType::of_struct::<T>()
+    }
+
+    fn from_array_ref(array: &'a ArrayRef) -> impl Iterator<Item = Self> + Send {
+        unwrap_rows(Self::try_from_array_ref(array))
+    }
+
+    fn try_from_array_ref(
+        array: &'a ArrayRef,
+    ) -> Result<impl Iterator<Item = Result<Self>> + Send> {
+        T::try_from_struct_array(as_struct(array)?)
+    }
+}
+
+impl<'a, T: FromStructArray<'a>> FromArrayRef<'a> for Option<T> {
+    fn column_type() -> ColumnType {
+        ColumnType::of_struct::<T>()
+    }
+
+    fn from_array_ref(array: &'a ArrayRef) -> impl Iterator<Item = Self> + Send {
+        unwrap_rows(Self::try_from_array_ref(array))
+    }
+
+    fn try_from_array_ref(
+        array: &'a ArrayRef,
+    ) -> Result<impl Iterator<Item = Result<Self>> + Send> {
+        __private::deny_skipped_rows::<T>(<T as FromStructArray>::SKIPS_ROWS)?;
+        let array = as_struct(array)?;
+        let nulls = array.logical_nulls();
+        let inner = T::try_from_struct_array(array)?;
+        // The children of a null struct may hold anything, so errors in them are ignored
+        Ok(inner.enumerate().map(move |(row, value)| {
+            if nulls.as_ref().is_some_and(|nulls| nulls.is_null(row)) {
+                Ok(None)
+            } else {
+                value.map(Some)
+            }
+        }))
+    }
 }
 
 macro_rules! impl_from_array_ref_primitive {
     ($native_ty:ty, $data_ty:ty) => {
+        impl_from_array_ref_primitive!($native_ty, $data_ty, <$data_ty as ArrowPrimitiveType>::DATA_TYPE);
+    };
+    ($native_ty:ty, $data_ty:ty, $data_type:expr) => {
+        impl ArrowField for $native_ty {
+            fn data_type() -> DataType {
+                $data_type
+            }
+        }
+
         impl<'a> FromArrayRef<'a> for Option<$native_ty> {
-            fn from_array_ref(array: &'a ArrayRef) -> impl Iterator<Item = Self> {
+            fn column_type() -> ColumnType {
+                ColumnType::Native($data_type)
+            }
+
+            fn from_array_ref(array: &'a ArrayRef) -> impl Iterator<Item = Self> + Send {
                 let array = array
                     .as_primitive_opt::<$data_ty>()
                     .expect(&format!(concat!(stringify!(Expected #data_ty), ", was {:?}"), array.data_type()));
                 array.iter()
             }
+
+            fn try_from_array_ref(array: &'a ArrayRef) -> Result<impl Iterator<Item = Result<Self>> + Send> {
+                let array = array
+                    .as_primitive_opt::<$data_ty>()
+                    .ok_or_else(|| Error::unexpected_type(stringify!($data_ty), array.data_type()))?;
+                Ok(array.iter().map(Ok))
+            }
+
+            fn coerce_from_array_ref(array: &'a ArrayRef) -> Result<impl Iterator<Item = Result<Self>> + Send> {
+                Ok(match array.as_primitive_opt::<$data_ty>() {
+                    Some(array) => Either::Left(array.iter().map(Ok)),
+                    None => Either::Right(coerce::coerce_primitive::<$data_ty>(array, &$data_type)?),
+                })
+            }
         }
 
         /// Will panic on null
         impl<'a> FromArrayRef<'a> for $native_ty {
-            fn from_array_ref(array: &'a ArrayRef) -> impl Iterator<Item = Self> {
+            fn column_type() -> ColumnType {
+                ColumnType::Native($data_type)
+            }
+
+            fn from_array_ref(array: &'a ArrayRef) -> impl Iterator<Item = Self> + Send {
                 let array = array
                     .as_primitive_opt::<$data_ty>()
                     .expect(&format!(concat!(stringify!(Expected #data_ty), ", was {:?}"), array.data_type()));
                 array.iter().map(Option::unwrap)
             }
+
+            fn try_from_array_ref(array: &'a ArrayRef) -> Result<impl Iterator<Item = Result<Self>> + Send> {
+                let array = array
+                    .as_primitive_opt::<$data_ty>()
+                    .ok_or_else(|| Error::unexpected_type(stringify!($data_ty), array.data_type()))?;
+                Ok(array
+                    .iter()
+                    .enumerate()
+                    .map(|(row, value)| value.ok_or(Error::UnexpectedNull { row })))
+            }
+
+            fn coerce_from_array_ref(array: &'a ArrayRef) -> Result<impl Iterator<Item = Result<Self>> + Send> {
+                Ok(<Option<$native_ty>>::coerce_from_array_ref(array)?
+                    .enumerate()
+                    .map(|(row, value)| value?.ok_or(Error::UnexpectedNull { row })))
+            }
         }
     };
 }
@@ -51,70 +437,145 @@ impl_from_array_ref_primitive!(u32, UInt32Type);
 impl_from_array_ref_primitive!(u64, UInt64Type);
 impl_from_array_ref_primitive!(f32, Float32Type);
 impl_from_array_ref_primitive!(f64, Float64Type);
+// Reads the unscaled value of any decimal, and integers are coerced with a scale of 0
+impl_from_array_ref_primitive!(i128, Decimal128Type, DataType::Decimal128(38, 0));
+
+impl ArrowField for bool {
+    fn data_type() -> DataType {
+        DataType::Boolean
+    }
+}
 
 impl<'a> FromArrayRef<'a> for Option<bool> {
-    fn from_array_ref(array: &'a ArrayRef) -> impl Iterator<Item = Self> {
+    fn column_type() -> ColumnType {
+        ColumnType::Native(DataType::Boolean)
+    }
+
+    fn from_array_ref(array: &'a ArrayRef) -> impl Iterator<Item = Self> + Send {
         let array = array.as_boolean();
         array.iter()
     }
+
+    fn try_from_array_ref(
+        array: &'a ArrayRef,
+    ) -> Result<impl Iterator<Item = Result<Self>> + Send> {
+        let array = array
+            .as_boolean_opt()
+            .ok_or_else(|| Error::unexpected_type("Boolean", array.data_type()))?;
+        Ok(array.iter().map(Ok))
+    }
+}
+
+impl ArrowField for String {
+    fn data_type() -> DataType {
+        DataType::Utf8
+    }
+}
+
+impl ArrowField for &str {
+    fn data_type() -> DataType {
+        DataType::Utf8
+    }
 }
 
 impl<'a> FromArrayRef<'a> for Option<String> {
-    fn from_array_ref(array: &'a ArrayRef) -> impl Iterator<Item = Self> {
-        let res: Box<dyn Iterator<Item = Self>> = match array.data_type() {
+    fn column_type() -> ColumnType {
+        ColumnType::Native(DataType::Utf8)
+    }
+
+    fn from_array_ref(array: &'a ArrayRef) -> impl Iterator<Item = Self> + Send {
+        unwrap_rows(Self::try_from_array_ref(array))
+    }
+
+    fn try_from_array_ref(
+        array: &'a ArrayRef,
+    ) -> Result<impl Iterator<Item = Result<Self>> + Send> {
+        let res: Box<dyn Iterator<Item = Result<Self>> + Send> = match array.data_type() {
             DataType::Utf8 => {
                 let array = array.as_string::<i32>();
-                Box::new(array.iter().map(|s| s.map(|s| s.to_string())))
+                Box::new(array.iter().map(|s| Ok(s.map(|s| s.to_string()))))
             }
             DataType::LargeUtf8 => {
                 let array = array.as_string::<i64>();
-                Box::new(array.iter().map(|s| s.map(|s| s.to_string())))
-            }
-            _ => {
-                panic!("Expected String, was {:?}", array.data_type())
+                Box::new(array.iter().map(|s| Ok(s.map(|s| s.to_string()))))
             }
+            _ => return Err(Error::unexpected_type("String", array.data_type())),
         };
-        res
+        Ok(res)
     }
 }
 
 impl<'a> FromArrayRef<'a> for Option<&'a str> {
-    fn from_array_ref(array: &'a ArrayRef) -> impl Iterator<Item = Self> {
-        let res: Box<dyn Iterator<Item = Self>> = match array.data_type() {
+    fn column_type() -> ColumnType {
+        ColumnType::Native(DataType::Utf8)
+    }
+
+    fn from_array_ref(array: &'a ArrayRef) -> impl Iterator<Item = Self> + Send {
+        unwrap_rows(Self::try_from_array_ref(array))
+    }
+
+    fn try_from_array_ref(
+        array: &'a ArrayRef,
+    ) -> Result<impl Iterator<Item = Result<Self>> + Send> {
+        let res: Box<dyn Iterator<Item = Result<Self>> + Send> = match array.data_type() {
             DataType::Utf8 => {
                 let array = array.as_string::<i32>();
-                Box::new(array.iter())
+                Box::new(array.iter().map(Ok))
             }
             DataType::LargeUtf8 => {
                 let array = array.as_string::<i64>();
-                Box::new(array.iter())
-            }
-            _ => {
-                panic!("Expected String, was {:?}", array.data_type())
+                Box::new(array.iter().map(Ok))
             }
+            _ => return Err(Error::unexpected_type("String", array.data_type())),
         };
-        res
+        Ok(res)
+    }
+}
+
+impl ArrowField for Bytes {
+    fn data_type() -> DataType {
+        DataType::Binary
+    }
+}
+
+impl ArrowField for &[u8] {
+    fn data_type() -> DataType {
+        DataType::Binary
     }
 }
 
 impl<'a> FromArrayRef<'a> for Option<Bytes> {
-    fn from_array_ref(array: &'a ArrayRef) -> impl Iterator<Item = Self> {
-        let res: Box<dyn Iterator<Item = Self>> = match array.data_type() {
+    fn column_type() -> ColumnType {
+        ColumnType::Native(DataType::Binary)
+    }
+
+    fn from_array_ref(array: &'a ArrayRef) -> impl Iterator<Item = Self> + Send {
+        unwrap_rows(Self::try_from_array_ref(array))
+    }
+
+    fn try_from_array_ref(
+        array: &'a ArrayRef,
+    ) -> Result<impl Iterator<Item = Result<Self>> + Send> {
+        let res: Box<dyn Iterator<Item = Result<Self>> + Send> = match array.data_type() {
             DataType::Binary => {
                 let array = array.as_binary::<i32>();
-                Box::new(array.iter()
-                    .map(|bytes| bytes.map(|bytes| Bytes::from(bytes.to_vec()))))
+                Box::new(
+                    array
+                        .iter()
+                        .map(|bytes| Ok(bytes.map(|bytes| Bytes::from(bytes.to_vec())))),
+                )
             }
             DataType::LargeBinary => {
                 let array = array.as_binary::<i64>();
-                Box::new(array.iter()
-                    .map(|bytes| bytes.map(|bytes| Bytes::from(bytes.to_vec()))))
-            }
-            _ => {
-                panic!("Expected String, was {:?}", array.data_type())
+                Box::new(
+                    array
+                        .iter()
+                        .map(|bytes| Ok(bytes.map(|bytes| Bytes::from(bytes.to_vec())))),
+                )
             }
+            _ => return Err(Error::unexpected_type("Binary", array.data_type())),
         };
-        res
+        Ok(res)
     }
 }
 
@@ -122,25 +583,43 @@ impl<'a, 'c> FromArrayRef<'a> for Option<&'c [u8]>
 where
     'a: 'c,
 {
-    fn from_array_ref(array: &'a ArrayRef) -> impl Iterator<Item = Option<&'c [u8]>> {
-        let res: Box<dyn Iterator<Item = Self>> = match array.data_type() {
+    fn column_type() -> ColumnType {
+        ColumnType::Native(DataType::Binary)
+    }
+
+    fn from_array_ref(array: &'a ArrayRef) -> impl Iterator<Item = Option<&'c [u8]>> + Send {
+        unwrap_rows(Self::try_from_array_ref(array))
+    }
+
+    fn try_from_array_ref(
+        array: &'a ArrayRef,
+    ) -> Result<impl Iterator<Item = Result<Option<&'c [u8]>>> + Send> {
+        let res: Box<dyn Iterator<Item = Result<Self>> + Send> = match array.data_type() {
             DataType::Binary => {
                 let array = array.as_binary::<i32>();
-                Box::new(array.iter())
+                Box::new(array.iter().map(Ok))
             }
             DataType::LargeBinary => {
                 let array = array.as_binary::<i64>();
-                Box::new(array.iter())
-            }
-            _ => {
-                panic!("Expected Binary, was {:?}", array.data_type())
+                Box::new(array.iter().map(Ok))
             }
+            _ => return Err(Error::unexpected_type("Binary", array.data_type())),
         };
-        res
+        Ok(res)
+    }
+}
+
+impl<T: ArrowField> ArrowField for Vec<T> {
+    fn data_type() -> DataType {
+        DataType::List(Arc::new(Field::new_list_field(T::data_type(), T::NULLABLE)))
     }
 }
 
 impl<'a, T: FromArrayRef<'a> + Debug + 'a> FromArrayRef<'a> for Option<Vec<T>> {
+    fn column_type() -> ColumnType {
+        ColumnType::List(Box::new(T::column_type()))
+    }
+
     // TODO: Needs extensive testing.
     // This is a bit verbose, but the naive implementation below is too slow:
     // array.iter()
@@ -149,55 +628,242 @@ impl<'a, T: FromArrayRef<'a> + Debug + 'a> FromArrayRef<'a> for Option<Vec<T>> {
     // We must use array.values() directly and handle the offsets, as we cannot call
     // T::from_array_ref in any kind of loop.
     // Could be room for more optimization by not using iterators?
-    fn from_array_ref(array: &'a ArrayRef) -> impl Iterator<Item = Self> {
-        fn helper<'a, O: OffsetSizeTrait + Into<i64>, T: FromArrayRef<'a> + Debug + 'a>(
-            array: &'a GenericListArray<O>,
-        ) -> impl Iterator<Item = Option<Vec<T>>> + 'a {
-            let nulls = array.logical_nulls();
-            let mut inner = T::from_array_ref(array.values());
-            let mut current_position = 0;
-
-            std::iter::from_fn(move || {
-                if current_position >= array.len() {
-                    return None;
-                }
-
-                let len = array.value_length(current_position).into();
-                let is_null = nulls
-                    .as_ref()
-                    .map(|buffer| buffer.is_null(current_position))
-                    .unwrap_or_default();
-                let res = if is_null {
-                    for _ in 0..len {
-                        // This can happen if record batch has values which are nulled. It's weird to construct RecordBatches this way, but it's possible
-                        let _ = inner.next().unwrap();
-                    }
-                    None
-                } else {
-                    let mut out = Vec::with_capacity(len as usize);
-                    for _ in 0..len {
-                        out.push(inner.next().unwrap());
-                    }
-                    Some(out)
-                };
-                current_position += 1;
-                Some(res)
-            })
+    fn from_array_ref(array: &'a ArrayRef) -> impl Iterator<Item = Self> + Send {
+        fn collect<T>(inner: &mut impl Iterator<Item = T>, len: usize, _row: usize) -> Vec<T> {
+            let mut out = Vec::with_capacity(len);
+            for _ in 0..len {
+                out.push(inner.next().unwrap());
+            }
+            out
         }
 
-        let res: Box<dyn Iterator<Item = Self>> = match array.data_type() {
+        __private::deny_skipped_rows::<T>(T::SKIPS_ROWS).unwrap_or_else(|error| panic!("{error}"));
+        let res: Box<dyn Iterator<Item = Self> + Send> = match array.data_type() {
             DataType::List(_) => {
                 let array = array.as_list::<i32>();
-                Box::new(helper(array))
+                Box::new(list_rows(array, T::from_array_ref(array.values()), collect))
             }
             DataType::LargeList(_) => {
                 let array = array.as_list::<i64>();
-                Box::new(helper(array))
+                Box::new(list_rows(array, T::from_array_ref(array.values()), collect))
             }
             _ => {
-                panic!("Expected Binary, was {:?}", array.data_type())
+                panic!("Expected List, was {:?}", array.data_type())
             }
         };
         res
     }
+
+    fn try_from_array_ref(
+        array: &'a ArrayRef,
+    ) -> Result<impl Iterator<Item = Result<Self>> + Send> {
+        __private::deny_skipped_rows::<T>(T::SKIPS_ROWS)?;
+        try_list_rows(array, T::try_from_array_ref)
+    }
+
+    fn coerce_from_array_ref(
+        array: &'a ArrayRef,
+    ) -> Result<impl Iterator<Item = Result<Self>> + Send> {
+        __private::deny_skipped_rows::<T>(T::SKIPS_ROWS)?;
+        try_list_rows(array, T::coerce_from_array_ref)
+    }
+}
+
+/// Reads lists, reading their values with `values`
+#[allow(clippy::type_complexity)]
+fn try_list_rows<'a, T: 'a, I: Iterator<Item = Result<T>> + Send + 'a>(
+    array: &'a ArrayRef,
+    values: impl Fn(&'a ArrayRef) -> Result<I>,
+) -> Result<Box<dyn Iterator<Item = Result<Option<Vec<T>>>> + Send + 'a>> {
+    fn collect<T>(
+        inner: &mut impl Iterator<Item = Result<T>>,
+        len: usize,
+        row: usize,
+    ) -> Result<Vec<T>> {
+        // All values of the list are read, so the next list still starts at its own values
+        let mut out = Ok(Vec::with_capacity(len));
+        for _ in 0..len {
+            match (inner.next().unwrap(), &mut out) {
+                (Ok(value), Ok(values)) => values.push(value),
+                (Err(error), Ok(_)) => out = Err(error.at_row(row)),
+                (_, Err(_)) => {}
+            }
+        }
+        out
+    }
+
+    Ok(match array.data_type() {
+        DataType::List(_) => {
+            let array = array.as_list::<i32>();
+            let inner = values(array.values())?;
+            Box::new(list_rows(array, inner, collect).map(Option::transpose))
+        }
+        DataType::LargeList(_) => {
+            let array = array.as_list::<i64>();
+            let inner = values(array.values())?;
+            Box::new(list_rows(array, inner, collect).map(Option::transpose))
+        }
+        _ => return Err(Error::unexpected_type("List", array.data_type())),
+    })
+}
+
+/// Splits the values of `array`, read by `inner`, into one list per row using `collect`, which
+/// is given the length and row of the list.
+fn list_rows<'a, O: OffsetSizeTrait + Into<i64>, I: Iterator + 'a, R>(
+    array: &'a GenericListArray<O>,
+    mut inner: I,
+    collect: impl Fn(&mut I, usize, usize) -> R + 'a,
+) -> impl Iterator<Item = Option<R>> + 'a {
+    let nulls = array.logical_nulls();
+    let mut current_position = 0;
+    // The values of a sliced array start at its first offset, not at the first value
+    let mut skip = array.value_offsets()[0].as_usize();
+
+    std::iter::from_fn(move || {
+        if current_position >= array.len() {
+            return None;
+        }
+        if skip > 0 {
+            inner.nth(skip - 1);
+            skip = 0;
+        }
+
+        let len = array.value_length(current_position).into() as usize;
+        let is_null = nulls
+            .as_ref()
+            .map(|buffer| buffer.is_null(current_position))
+            .unwrap_or_default();
+        let res = if is_null {
+            for _ in 0..len {
+                // This can happen if record batch has values which are nulled. It's weird to construct RecordBatches this way, but it's possible
+                let _ = inner.next().unwrap();
+            }
+            None
+        } else {
+            Some(collect(&mut inner, len, current_position))
+        };
+        current_position += 1;
+        Some(res)
+    })
+}
+
+/// Used by the code generated by `#[derive(Deserialize)]`. Not public API.
+#[doc(hidden)]
+pub mod __private {
+    use crate::{ArrowField, FromArrayRef};
+    use crate::{Error, Result};
+    pub use arrow;
+    use arrow::array::{Array, ArrayRef, AsArray, StructArray};
+    use arrow::buffer::NullBuffer;
+    use arrow::compute::CastOptions;
+    use std::borrow::Cow;
+
+    /// Finds the column at `path` by walking through struct columns, for `#[arrow_struct(path)]`.
+    ///
+    /// Returns the column, together with the combined nulls of the structs along the way, or
+    /// `None` if a column is missing.
+    pub fn column_by_path<'a>(
+        array: &'a StructArray,
+        path: &[&str],
+    ) -> Result<Option<(&'a ArrayRef, Option<NullBuffer>)>> {
+        let (last, parents) = path.split_last().expect("path is not empty");
+        let mut array = array;
+        let mut nulls: Option<NullBuffer> = None;
+        for (depth, name) in parents.iter().enumerate() {
+            let Some(column) = array.column_by_name(name) else {
+                return Ok(None);
+            };
+            array = column.as_struct_opt().ok_or_else(|| {
+                Error::unexpected_type("Struct", column.data_type())
+                    .in_column(path[..=depth].join("."))
+            })?;
+            nulls = NullBuffer::union(nulls.as_ref(), array.logical_nulls().as_ref());
+        }
+        Ok(array.column_by_name(last).map(|column| (column, nulls)))
+    }
+
+    /// Finds the column matching the first of `names` that matches any, comparing normalized names,
+    /// for `#[arrow_struct(match_case)]` and `#[arrow_struct(normalize)]`.
+    ///
+    /// It's an error if more than one column matches a name.
+    pub fn column_by_normalized_name<'a>(
+        array: &'a StructArray,
+        names: &[&str],
+        normalize: fn(&str) -> Cow<'_, str>,
+    ) -> Result<Option<&'a ArrayRef>> {
+        for name in names {
+            let normalized = normalize(name);
+            let mut matches = array
+                .fields()
+                .iter()
+                .zip(array.columns())
+                .filter(|(field, _)| normalize(field.name()) == normalized);
+            let Some((_, column)) = matches.next() else {
+                continue;
+            };
+            if matches.next().is_some() {
+                let matches = array
+                    .fields()
+                    .iter()
+                    .filter(|field| normalize(field.name()) == normalized)
+                    .map(|field| field.name().clone())
+                    .collect();
+                return Err(Error::AmbiguousColumn {
+                    name: name.to_string(),
+                    matches,
+                });
+            }
+            return Ok(Some(column));
+        }
+        Ok(None)
+    }
+
+    /// Returns an error if `T` skips rows, for readers of nested values, which match them up with
+    /// their rows by position.
+    pub fn deny_skipped_rows<T>(skips_rows: bool) -> Result<()> {
+        if skips_rows {
+            return Err(Error::custom(format!(
+                "{} skips rows, so it can only be read at the outermost level",
+                std::any::type_name::<T>()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Reads `T` from `array` after casting it to `T`'s data type, for `#[arrow_struct(cast)]`.
+    ///
+    /// The cast array only lives in this function, so the values are read up front.
+    pub fn cast<'a, T>(array: &'a ArrayRef) -> Result<impl Iterator<Item = Result<T>> + 'a>
+    where
+        T: for<'b> FromArrayRef<'b> + ArrowField + 'a,
+    {
+        let data_type = T::data_type();
+        if array.data_type() == &data_type {
+            return Ok(Either::Left(T::try_from_array_ref(array)?));
+        }
+        let options = CastOptions {
+            safe: false,
+            ..Default::default()
+        };
+        let cast = arrow::compute::cast_with_options(array, &data_type, &options)?;
+        let rows = T::try_from_array_ref(&cast)?.collect::<Vec<_>>();
+        Ok(Either::Right(rows.into_iter()))
+    }
+
+    /// One of two iterators with the same item type
+    pub enum Either<L, R> {
+        Left(L),
+        Right(R),
+    }
+
+    impl<L: Iterator, R: Iterator<Item = L::Item>> Iterator for Either<L, R> {
+        type Item = L::Item;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            match self {
+                Either::Left(left) => left.next(),
+                Either::Right(right) => right.next(),
+            }
+        }
+    }
 }
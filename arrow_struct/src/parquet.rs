@@ -0,0 +1,253 @@
+//! Reading and writing Parquet files with structs, behind the `parquet` feature.
+use crate::{batch_rows, ColumnType, Error, FromStructArray, Result, ToStructArray};
+use arrow::array::{BooleanArray, RecordBatch, StructArray};
+use arrow::datatypes::{DataType, Schema};
+use arrow::error::ArrowError;
+use parquet::arrow::arrow_reader::{ArrowPredicateFn, ParquetRecordBatchReaderBuilder, RowFilter};
+use parquet::arrow::{arrow_to_parquet_schema, ArrowWriter, ProjectionMask};
+use parquet::basic::{Compression, Encoding};
+use parquet::file::properties::{WriterProperties, WriterPropertiesBuilder};
+use parquet::file::reader::ChunkReader;
+use parquet::format::FileMetaData;
+use parquet::schema::types::SchemaDescriptor;
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Reads the rows of a Parquet file as `T`, decoding only the columns `T` is read from.
+///
+/// Like `#[arrow_struct(cast)]`, the rows of every batch are read up front, so `T` can't borrow
+/// from the arrays.
+pub fn read<T>(file: impl ChunkReader + 'static) -> Result<impl Iterator<Item = Result<T>>>
+where
+    T: for<'a> FromStructArray<'a>,
+{
+    let builder = ParquetRecordBatchReaderBuilder::try_new(file)?;
+    T::validate_fields(builder.schema().fields())?;
+    let mask = projection::<T>(builder.schema(), builder.parquet_schema());
+    let reader = builder.with_projection(mask).build()?;
+    Ok(reader.flat_map(batch_rows))
+}
+
+/// Reads the rows of a Parquet file as `T`, like [`read`], but only those for which `predicate`
+/// returns true.
+///
+/// The predicate is given the row read as `K`, which should only have the few columns needed to
+/// filter the rows. Parquet skips decoding the other columns of rows that are filtered out.
+///
+/// `K` can't skip rows (`#[arrow_struct(skip_null_rows)]`), as every row needs a value.
+pub fn read_filtered<T, K>(
+    file: impl ChunkReader + 'static,
+    mut predicate: impl FnMut(&K) -> bool + Send + 'static,
+) -> Result<impl Iterator<Item = Result<T>>>
+where
+    T: for<'a> FromStructArray<'a>,
+    K: for<'a> FromStructArray<'a>,
+{
+    crate::__private::deny_skipped_rows::<K>(<K as FromStructArray>::SKIPS_ROWS)?;
+    let builder = ParquetRecordBatchReaderBuilder::try_new(file)?;
+    T::validate_fields(builder.schema().fields())?;
+    K::validate_fields(builder.schema().fields())?;
+    let filter_mask = projection::<K>(builder.schema(), builder.parquet_schema());
+    let filter = ArrowPredicateFn::new(filter_mask, move |batch: RecordBatch| {
+        let array = StructArray::from(batch);
+        let filter = K::try_from_columns(&array)
+            .and_then(|rows| {
+                rows.map(|row| row.map(|row| predicate(&row)))
+                    .collect::<Result<Vec<_>>>()
+            })
+            // Turned back into our error by `From<ParquetError>`
+            .map_err(|error| ArrowError::ExternalError(Box::new(error)))?;
+        Ok(BooleanArray::from(filter))
+    });
+    let mask = projection::<T>(builder.schema(), builder.parquet_schema());
+    let reader = builder
+        .with_row_filter(RowFilter::new(vec![Box::new(filter)]))
+        .with_projection(mask)
+        .build()?;
+    Ok(reader.flat_map(batch_rows))
+}
+
+/// The projection of a Parquet file with the given schema to the leaf columns `T` is read from.
+///
+/// Nested structs are projected to the columns they read too. Nothing is projected away from a
+/// struct that reads columns by position, which would change when other columns are dropped, or
+/// that denies unknown columns.
+pub fn projection<'a, T: FromStructArray<'a>>(
+    schema: &Schema,
+    parquet_schema: &SchemaDescriptor,
+) -> ProjectionMask {
+    let mut leaves = Vec::new();
+    add_leaves(
+        &DataType::Struct(schema.fields().clone()),
+        ColumnType::of_struct::<T>(),
+        0,
+        &mut leaves,
+    );
+    ProjectionMask::leaves(parquet_schema, leaves)
+}
+
+/// Adds the Parquet leaves needed to read a column of `data_type` as `column_type`, where `first`
+/// is the index of its first leaf.
+fn add_leaves(
+    data_type: &DataType,
+    column_type: ColumnType,
+    first: usize,
+    leaves: &mut Vec<usize>,
+) {
+    match (column_type, data_type) {
+        (
+            ColumnType::Struct {
+                columns,
+                normalize,
+                deny_unknown_columns: false,
+            },
+            DataType::Struct(fields),
+        ) if !columns.is_empty() && columns.iter().all(|column| column.index.is_none()) => {
+            let mut first = first;
+            for (position, field) in fields.iter().enumerate() {
+                for column in columns
+                    .iter()
+                    .filter(|column| column.reads(position, field, normalize))
+                {
+                    add_leaves(field.data_type(), (column.column_type)(), first, leaves);
+                }
+                first += leaf_count(field.data_type());
+            }
+        }
+        (
+            ColumnType::List(item),
+            DataType::List(field) | DataType::LargeList(field) | DataType::FixedSizeList(field, _),
+        ) => add_leaves(field.data_type(), *item, first, leaves),
+        (_, data_type) => leaves.extend(first..first + leaf_count(data_type)),
+    }
+}
+
+/// The number of Parquet leaves a column of `data_type` is stored in
+fn leaf_count(data_type: &DataType) -> usize {
+    match data_type {
+        DataType::Struct(fields) => fields
+            .iter()
+            .map(|field| leaf_count(field.data_type()))
+            .sum(),
+        DataType::List(field)
+        | DataType::LargeList(field)
+        | DataType::FixedSizeList(field, _)
+        | DataType::Map(field, _) => leaf_count(field.data_type()),
+        _ => 1,
+    }
+}
+
+/// The number of rows [`Writer`] buffers before writing them as a batch
+const BATCH_SIZE: usize = 8192;
+
+/// Writes rows of `T` to a Parquet file.
+///
+/// The rows are buffered and written in batches. The file is only complete after [`Writer::close`].
+pub struct Writer<T, W: Write + Send> {
+    writer: ArrowWriter<W>,
+    rows: Vec<T>,
+}
+
+impl<T: ToStructArray, W: Write + Send> Writer<T, W> {
+    /// Creates a writer with default properties, apart from those set by attributes of `T` like
+    /// `#[arrow_struct(compression = "zstd(3)")]`.
+    pub fn try_new(writer: W) -> Result<Self> {
+        Self::try_new_with_properties(writer, WriterProperties::builder())
+    }
+
+    /// Creates a writer with the given properties, e.g.
+    /// `WriterProperties::builder().set_max_row_group_size(100_000)`.
+    ///
+    /// The properties set by attributes of `T` override those for the same columns.
+    pub fn try_new_with_properties(writer: W, properties: WriterPropertiesBuilder) -> Result<Self> {
+        let schema = Arc::new(Schema::new(T::fields()));
+        let properties = column_properties::<T>(&schema, properties)?.build();
+        let writer = ArrowWriter::try_new(writer, schema, Some(properties))?;
+        Ok(Writer {
+            writer,
+            rows: Vec::with_capacity(BATCH_SIZE),
+        })
+    }
+
+    pub fn write(&mut self, row: T) -> Result<()> {
+        self.rows.push(row);
+        if self.rows.len() >= BATCH_SIZE {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered rows. They may still be buffered by the underlying `ArrowWriter` until
+    /// their row group is complete.
+    pub fn flush(&mut self) -> Result<()> {
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+        let batch = T::to_record_batch(&self.rows);
+        self.writer.write(&batch)?;
+        self.rows.clear();
+        Ok(())
+    }
+
+    /// Writes the remaining rows and the footer of the file.
+    pub fn close(mut self) -> Result<FileMetaData> {
+        self.flush()?;
+        Ok(self.writer.close()?)
+    }
+}
+
+/// Applies the [`ColumnOptions`](crate::ColumnOptions) of `T` to all Parquet columns nested in
+/// their columns.
+fn column_properties<T: ToStructArray>(
+    schema: &Schema,
+    mut properties: WriterPropertiesBuilder,
+) -> Result<WriterPropertiesBuilder> {
+    let parquet_schema = arrow_to_parquet_schema(schema)?;
+    for options in T::column_options() {
+        let name = options.path.join(".");
+        let compression = options
+            .compression
+            .map(Compression::from_str)
+            .transpose()
+            .map_err(|error| Error::from(error).in_column(&name))?;
+        let encoding = options
+            .encoding
+            .map(Encoding::from_str)
+            .transpose()
+            .map_err(|error| Error::from(error).in_column(&name))?;
+        // The writer panics on dictionary encodings, which are enabled with `dictionary` instead
+        if let Some(encoding @ (Encoding::RLE_DICTIONARY | Encoding::PLAIN_DICTIONARY)) = encoding {
+            return Err(Error::custom(format!(
+                "{encoding} can't be set as the encoding, use `dictionary` instead"
+            ))
+            .in_column(&name));
+        }
+        let columns = parquet_schema
+            .columns()
+            .iter()
+            .map(|column| column.path())
+            .filter(|path| {
+                path.parts()
+                    .iter()
+                    .map(String::as_str)
+                    .take(options.path.len())
+                    .eq(options.path.iter().copied())
+            });
+        for path in columns {
+            if let Some(compression) = compression {
+                properties = properties.set_column_compression(path.clone(), compression);
+            }
+            if let Some(encoding) = encoding {
+                properties = properties.set_column_encoding(path.clone(), encoding);
+            }
+            if let Some(dictionary) = options.dictionary {
+                properties = properties.set_column_dictionary_enabled(path.clone(), dictionary);
+            }
+            if let Some(bloom_filter) = options.bloom_filter {
+                properties = properties.set_column_bloom_filter_enabled(path.clone(), bloom_filter);
+            }
+        }
+    }
+    Ok(properties)
+}
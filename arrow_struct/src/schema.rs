@@ -0,0 +1,143 @@
+//! The schemas text formats are read with, which have to be given the data types of the columns.
+use crate::{Column, ColumnType, FromStructArray};
+use arrow::datatypes::{DataType, Field, Fields, Schema};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// The schema to read `T` with from a text format, with the fields found in the data (e.g. the
+/// names in a CSV header), where the columns `T` reads have the data types it reads them as.
+///
+/// Fields are matched up with columns like [`FromStructArray::validate_fields`] does. Fields `T`
+/// doesn't read, or reads with a custom decoder, keep their data types. With `add_missing`, named
+/// columns not among the fields are added (nested columns always are).
+pub(crate) fn read_schema<'a, T: FromStructArray<'a>>(
+    fields: &Fields,
+    add_missing: bool,
+) -> Schema {
+    let fields = read_fields(fields, &T::columns(), T::normalize_name, add_missing);
+    Schema::new(nullable(&fields))
+}
+
+fn read_fields(
+    fields: &Fields,
+    columns: &[Column],
+    normalize: fn(&str) -> Cow<'_, str>,
+    add_missing: bool,
+) -> Fields {
+    let mut read = fields
+        .iter()
+        .enumerate()
+        .map(|(position, field)| {
+            let column_type = columns
+                .iter()
+                .filter(|column| column.reads(position, field, normalize))
+                .map(|column| (column.column_type)())
+                .reduce(merge);
+            match column_type
+                .and_then(|column_type| read_data_type(column_type, Some(field.data_type())))
+            {
+                Some(data_type) => field.as_ref().clone().with_data_type(data_type),
+                None => field.as_ref().clone(),
+            }
+        })
+        .collect::<Vec<_>>();
+    if add_missing {
+        // Columns sharing a name, like `path`s with the same root, are added once
+        let mut missing: Vec<(&str, ColumnType)> = Vec::new();
+        for column in columns.iter().filter(|column| column.index.is_none()) {
+            let found = fields
+                .iter()
+                .enumerate()
+                .any(|(position, field)| column.reads(position, field, normalize));
+            if found {
+                continue;
+            }
+            match missing
+                .iter_mut()
+                .find(|(name, _)| normalize(name) == normalize(column.name()))
+            {
+                Some((_, column_type)) => {
+                    *column_type = merge(column_type.clone(), (column.column_type)())
+                }
+                None => missing.push((column.name(), (column.column_type)())),
+            }
+        }
+        read.extend(missing.into_iter().filter_map(|(name, column_type)| {
+            Some(Field::new(name, read_data_type(column_type, None)?, true))
+        }));
+    }
+    read.into()
+}
+
+/// The data type to read a column as `column_type` from, if it's known. `inferred` is the data type
+/// found in the data, which custom decoders and the columns structs don't read keep.
+fn read_data_type(column_type: ColumnType, inferred: Option<&DataType>) -> Option<DataType> {
+    match column_type {
+        ColumnType::Unknown => inferred.cloned(),
+        ColumnType::Native(data_type) => Some(data_type),
+        ColumnType::List(item) => {
+            let inferred = match inferred {
+                Some(DataType::List(item) | DataType::LargeList(item)) => Some(item.data_type()),
+                _ => None,
+            };
+            let item = read_data_type(*item, inferred)?;
+            Some(DataType::List(Arc::new(Field::new_list_field(item, true))))
+        }
+        ColumnType::Struct {
+            columns, normalize, ..
+        } => {
+            let fields = match inferred {
+                Some(DataType::Struct(fields)) => fields.clone(),
+                _ => Fields::empty(),
+            };
+            Some(DataType::Struct(read_fields(
+                &fields, &columns, normalize, true,
+            )))
+        }
+    }
+}
+
+/// Combines the types of columns reading the same field, e.g. of a nested struct and a `path` into it.
+fn merge(first: ColumnType, second: ColumnType) -> ColumnType {
+    match (first, second) {
+        (
+            ColumnType::Struct {
+                mut columns,
+                normalize,
+                deny_unknown_columns,
+            },
+            ColumnType::Struct {
+                columns: more_columns,
+                deny_unknown_columns: deny_more,
+                ..
+            },
+        ) => {
+            columns.extend(more_columns);
+            ColumnType::Struct {
+                columns,
+                normalize,
+                deny_unknown_columns: deny_unknown_columns || deny_more,
+            }
+        }
+        (ColumnType::List(first), ColumnType::List(second)) => {
+            ColumnType::List(Box::new(merge(*first, *second)))
+        }
+        (ColumnType::Unknown, second) => second,
+        (first, _) => first,
+    }
+}
+
+/// The fields, with all fields nested in them, made nullable.
+///
+/// Text formats can leave out any value, and it's up to the target type what to do with nulls.
+fn nullable(fields: &Fields) -> Fields {
+    fn nullable_field(field: &Field) -> Field {
+        let data_type = match field.data_type() {
+            DataType::Struct(fields) => DataType::Struct(nullable(fields)),
+            DataType::List(item) => DataType::List(Arc::new(nullable_field(item))),
+            data_type => data_type.clone(),
+        };
+        field.clone().with_data_type(data_type).with_nullable(true)
+    }
+    fields.iter().map(|field| nullable_field(field)).collect()
+}
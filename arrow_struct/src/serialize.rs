@@ -0,0 +1,209 @@
+//! Writing Rust values to arrays, the other direction of [`FromArrayRef`](crate::FromArrayRef).
+use crate::ArrowField;
+use arrow::array::{
+    ArrayRef, BinaryArray, BooleanArray, ListArray, PrimitiveArray, RecordBatch, StringArray,
+    StructArray,
+};
+use arrow::buffer::{NullBuffer, OffsetBuffer};
+use arrow::datatypes::{
+    DataType, Decimal128Type, Field, Fields, Float32Type, Float64Type, Int16Type, Int32Type,
+    Int64Type, Int8Type, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
+use bytes::Bytes;
+use std::sync::Arc;
+
+/// Writes values of `Self` to an array of [`ArrowField::data_type`].
+pub trait ToArrayRef: ArrowField {
+    /// Writes the values to an array, where `None` is null.
+    ///
+    /// The iterator is cloned to write the values of nested arrays, e.g. once for every field of
+    /// a struct.
+    fn to_array_ref<'b>(values: impl Iterator<Item = Option<&'b Self>> + Clone) -> ArrayRef
+    where
+        Self: 'b;
+
+    /// Settings for writing the columns of `Self` to a file, relative to the column of `Self`.
+    fn column_options() -> Vec<ColumnOptions> {
+        vec![]
+    }
+}
+
+/// Writes `Self` to the columns of a [`StructArray`]. This is what `#[derive(Serialize)]` implements.
+///
+/// Every `ToStructArray` type is also [`ToArrayRef`] and [`ArrowField`], with a struct data type.
+pub trait ToStructArray {
+    /// The fields of the struct `Self` is written to.
+    fn fields() -> Fields;
+
+    /// Writes the values to one array for every field of [`fields`](Self::fields).
+    ///
+    /// This lets `#[arrow_struct(flatten)]` write a struct to the columns of its parent.
+    fn to_columns<'b>(values: impl Iterator<Item = Option<&'b Self>> + Clone) -> Vec<ArrayRef>
+    where
+        Self: 'b;
+
+    /// Settings for writing the columns of `Self` to a file, from attributes like
+    /// `#[arrow_struct(compression = "zstd(3)")]`.
+    fn column_options() -> Vec<ColumnOptions> {
+        vec![]
+    }
+
+    fn to_struct_array(values: &[Self]) -> StructArray
+    where
+        Self: Sized,
+    {
+        struct_array::<Self>(values.iter().map(Some))
+    }
+
+    fn to_record_batch(values: &[Self]) -> RecordBatch
+    where
+        Self: Sized,
+    {
+        RecordBatch::from(Self::to_struct_array(values))
+    }
+}
+
+/// Settings for writing a column (or all columns nested in it) to a file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnOptions {
+    /// The names of the column and the struct columns it's nested in, starting from the outermost
+    pub path: Vec<&'static str>,
+    /// `#[arrow_struct(compression = "...")]`, e.g. `zstd(3)`
+    pub compression: Option<&'static str>,
+    /// `#[arrow_struct(encoding = "...")]`, e.g. `delta_binary_packed`
+    pub encoding: Option<&'static str>,
+    /// `#[arrow_struct(dictionary)]` or `#[arrow_struct(dictionary = false)]`
+    pub dictionary: Option<bool>,
+    /// `#[arrow_struct(bloom_filter)]` or `#[arrow_struct(bloom_filter = false)]`
+    pub bloom_filter: Option<bool>,
+}
+
+fn struct_array<'b, T: ToStructArray + 'b>(
+    values: impl Iterator<Item = Option<&'b T>> + Clone,
+) -> StructArray {
+    let nulls = nulls(values.clone());
+    let fields = T::fields();
+    if fields.is_empty() {
+        return StructArray::new_empty_fields(values.count(), nulls);
+    }
+    StructArray::new(fields, T::to_columns(values), nulls)
+}
+
+/// The nulls of the values, if there are any
+fn nulls<T>(values: impl Iterator<Item = Option<T>>) -> Option<NullBuffer> {
+    Some(NullBuffer::from_iter(values.map(|value| value.is_some())))
+        .filter(|nulls| nulls.null_count() > 0)
+}
+
+impl<T: ToStructArray> ArrowField for T {
+    fn data_type() -> DataType {
+        DataType::Struct(T::fields())
+    }
+}
+
+impl<T: ToStructArray> ToArrayRef for T {
+    fn to_array_ref<'b>(values: impl Iterator<Item = Option<&'b Self>> + Clone) -> ArrayRef
+    where
+        Self: 'b,
+    {
+        Arc::new(struct_array(values))
+    }
+
+    fn column_options() -> Vec<ColumnOptions> {
+        <T as ToStructArray>::column_options()
+    }
+}
+
+impl<T: ToArrayRef> ToArrayRef for Option<T> {
+    fn to_array_ref<'b>(values: impl Iterator<Item = Option<&'b Self>> + Clone) -> ArrayRef
+    where
+        Self: 'b,
+    {
+        T::to_array_ref(values.map(|value| value.and_then(Option::as_ref)))
+    }
+
+    fn column_options() -> Vec<ColumnOptions> {
+        T::column_options()
+    }
+}
+
+macro_rules! impl_to_array_ref_primitive {
+    ($native_ty:ty, $data_ty:ty) => {
+        impl ToArrayRef for $native_ty {
+            fn to_array_ref<'b>(
+                values: impl Iterator<Item = Option<&'b Self>> + Clone,
+            ) -> ArrayRef {
+                Arc::new(
+                    PrimitiveArray::<$data_ty>::from_iter(values.map(|value| value.copied()))
+                        .with_data_type(Self::data_type()),
+                )
+            }
+        }
+    };
+}
+
+impl_to_array_ref_primitive!(i8, Int8Type);
+impl_to_array_ref_primitive!(i16, Int16Type);
+impl_to_array_ref_primitive!(i32, Int32Type);
+impl_to_array_ref_primitive!(i64, Int64Type);
+impl_to_array_ref_primitive!(u8, UInt8Type);
+impl_to_array_ref_primitive!(u16, UInt16Type);
+impl_to_array_ref_primitive!(u32, UInt32Type);
+impl_to_array_ref_primitive!(u64, UInt64Type);
+impl_to_array_ref_primitive!(f32, Float32Type);
+impl_to_array_ref_primitive!(f64, Float64Type);
+impl_to_array_ref_primitive!(i128, Decimal128Type);
+
+impl ToArrayRef for bool {
+    fn to_array_ref<'b>(values: impl Iterator<Item = Option<&'b Self>> + Clone) -> ArrayRef {
+        Arc::new(BooleanArray::from_iter(values.map(|value| value.copied())))
+    }
+}
+
+impl ToArrayRef for String {
+    fn to_array_ref<'b>(values: impl Iterator<Item = Option<&'b Self>> + Clone) -> ArrayRef {
+        Arc::new(StringArray::from_iter(
+            values.map(|value| value.map(String::as_str)),
+        ))
+    }
+}
+
+impl ToArrayRef for &str {
+    fn to_array_ref<'b>(values: impl Iterator<Item = Option<&'b Self>> + Clone) -> ArrayRef
+    where
+        Self: 'b,
+    {
+        Arc::new(StringArray::from_iter(values.map(|value| value.copied())))
+    }
+}
+
+impl ToArrayRef for Bytes {
+    fn to_array_ref<'b>(values: impl Iterator<Item = Option<&'b Self>> + Clone) -> ArrayRef {
+        Arc::new(BinaryArray::from_iter(
+            values.map(|value| value.map(Bytes::as_ref)),
+        ))
+    }
+}
+
+impl ToArrayRef for &[u8] {
+    fn to_array_ref<'b>(values: impl Iterator<Item = Option<&'b Self>> + Clone) -> ArrayRef
+    where
+        Self: 'b,
+    {
+        Arc::new(BinaryArray::from_iter(values.map(|value| value.copied())))
+    }
+}
+
+impl<T: ToArrayRef> ToArrayRef for Vec<T> {
+    fn to_array_ref<'b>(values: impl Iterator<Item = Option<&'b Self>> + Clone) -> ArrayRef
+    where
+        Self: 'b,
+    {
+        let offsets =
+            OffsetBuffer::from_lengths(values.clone().map(|value| value.map_or(0, Vec::len)));
+        let nulls = nulls(values.clone());
+        let items = T::to_array_ref(values.flat_map(|value| value.into_iter().flatten().map(Some)));
+        let field = Field::new_list_field(T::data_type(), T::NULLABLE);
+        Arc::new(ListArray::new(Arc::new(field), offsets, items, nulls))
+    }
+}
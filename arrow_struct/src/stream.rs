@@ -0,0 +1,44 @@
+//! Reading rows from async streams of batches, behind the `async` feature.
+use crate::{batch_rows, Error, FromStructArray, Result};
+use arrow::array::RecordBatch;
+use futures::{stream, Stream, StreamExt};
+
+/// Reads the rows of every batch of the stream as `T`.
+///
+/// The stream can be e.g. a Parquet `ParquetRecordBatchStream`, or a DataFusion
+/// `SendableRecordBatchStream` after `.map_err(ArrowError::from)`. Like the readers of files, the
+/// rows of every batch are read up front, so `T` can't borrow from the arrays.
+pub fn rows<T, E>(
+    batches: impl Stream<Item = std::result::Result<RecordBatch, E>>,
+) -> impl Stream<Item = Result<T>>
+where
+    T: for<'a> FromStructArray<'a>,
+    E: Into<Error>,
+{
+    batches.flat_map(|batch| stream::iter(batch_rows(validated::<T, E>(batch))))
+}
+
+/// Reads the rows of every batch of the stream as `T`, like [`rows`], but with one `Vec` of rows
+/// for every batch.
+///
+/// A batch with a row that can't be read is an error.
+pub fn batches<T, E>(
+    batches: impl Stream<Item = std::result::Result<RecordBatch, E>>,
+) -> impl Stream<Item = Result<Vec<T>>>
+where
+    T: for<'a> FromStructArray<'a>,
+    E: Into<Error>,
+{
+    batches.map(|batch| batch_rows(validated::<T, E>(batch)).into_iter().collect())
+}
+
+/// Checks the schema of every batch, as there is none before the first batch.
+fn validated<T, E>(batch: std::result::Result<RecordBatch, E>) -> Result<RecordBatch>
+where
+    T: for<'a> FromStructArray<'a>,
+    E: Into<Error>,
+{
+    let batch = batch.map_err(Into::into)?;
+    T::validate_fields(batch.schema().fields())?;
+    Ok(batch)
+}
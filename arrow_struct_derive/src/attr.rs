@@ -0,0 +1,307 @@
+use syn::meta::ParseNestedMeta;
+use syn::punctuated::Punctuated;
+use syn::{
+    parse_quote, Attribute, ExprPath, Ident, LitBool, LitInt, LitStr, Token, Type, WherePredicate,
+};
+
+pub(crate) type Bound = Punctuated<WherePredicate, Token![,]>;
+
+fn parse_bound(meta: &ParseNestedMeta) -> syn::Result<Bound> {
+    let bound: LitStr = meta.value()?.parse()?;
+    bound.parse_with(Punctuated::parse_terminated)
+}
+
+/// What to do when a non-`Option` field is null.
+#[derive(Clone, Copy)]
+pub(crate) enum OnNull {
+    /// Return an error for the row.
+    Error,
+    /// Use `Default::default()`.
+    Default,
+    /// Drop the row.
+    Skip,
+}
+
+impl OnNull {
+    fn parse(meta: &ParseNestedMeta) -> syn::Result<Self> {
+        let value: LitStr = meta.value()?.parse()?;
+        match value.value().as_str() {
+            "error" => Ok(OnNull::Error),
+            "default" => Ok(OnNull::Default),
+            "skip" => Ok(OnNull::Skip),
+            _ => Err(syn::Error::new(
+                value.span(),
+                "expected `error`, `default` or `skip`",
+            )),
+        }
+    }
+}
+
+/// How a field is converted from the type its column is read as.
+pub(crate) enum Convert {
+    /// `From<T>`
+    From(Type),
+    /// `TryFrom<T>`
+    TryFrom(Type),
+    /// `FromStr`
+    Parse,
+}
+
+/// `#[arrow_struct(...)]` attributes on the struct.
+pub(crate) struct ContainerAttrs {
+    pub on_null: Option<OnNull>,
+    pub deny_unknown_columns: bool,
+    pub coerce: bool,
+    pub bound: Option<Bound>,
+    /// The path of the arrow_struct crate in generated code
+    pub krate: syn::Path,
+    pub by_position: bool,
+    /// `match_case = "insensitive"`
+    pub case_insensitive: bool,
+    /// A function normalizing column names
+    pub normalize: Option<ExprPath>,
+}
+
+impl ContainerAttrs {
+    pub fn from_ast(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut out = ContainerAttrs {
+            on_null: None,
+            deny_unknown_columns: false,
+            coerce: false,
+            bound: None,
+            krate: parse_quote!(arrow_struct),
+            by_position: false,
+            case_insensitive: false,
+            normalize: None,
+        };
+        let mut krate = None;
+        for attr in attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("arrow_struct"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("on_null") {
+                    set_once(&meta, &mut out.on_null, OnNull::parse(&meta)?)
+                } else if meta.path.is_ident("skip_null_rows") {
+                    set_once(&meta, &mut out.on_null, OnNull::Skip)
+                } else if meta.path.is_ident("deny_unknown_columns") {
+                    out.deny_unknown_columns = true;
+                    Ok(())
+                } else if meta.path.is_ident("coerce") {
+                    out.coerce = true;
+                    Ok(())
+                } else if meta.path.is_ident("bound") {
+                    set_once(&meta, &mut out.bound, parse_bound(&meta)?)
+                } else if meta.path.is_ident("match_case") {
+                    let value: LitStr = meta.value()?.parse()?;
+                    out.case_insensitive = match value.value().as_str() {
+                        "sensitive" => false,
+                        "insensitive" => true,
+                        _ => {
+                            return Err(syn::Error::new(
+                                value.span(),
+                                "expected `sensitive` or `insensitive`",
+                            ))
+                        }
+                    };
+                    Ok(())
+                } else if meta.path.is_ident("normalize") {
+                    let path: LitStr = meta.value()?.parse()?;
+                    set_once(&meta, &mut out.normalize, path.parse()?)
+                } else if meta.path.is_ident("by_position") {
+                    out.by_position = true;
+                    Ok(())
+                } else if meta.path.is_ident("crate") {
+                    let path: LitStr = meta.value()?.parse()?;
+                    set_once(&meta, &mut krate, path.parse()?)
+                } else {
+                    Err(meta.error("unknown arrow_struct container attribute"))
+                }
+            })?;
+        }
+        if let Some(krate) = krate {
+            out.krate = krate;
+        }
+        Ok(out)
+    }
+}
+
+/// Where the value of a field comes from when its column is missing.
+pub(crate) enum FieldDefault {
+    /// `Default::default()`
+    Trait,
+    /// A function returning the value
+    Path(ExprPath),
+}
+
+/// `#[arrow_struct(...)]` attributes on a field.
+#[derive(Default)]
+pub(crate) struct FieldAttrs {
+    pub on_null: Option<OnNull>,
+    pub default: Option<FieldDefault>,
+    pub aliases: Vec<LitStr>,
+    pub coerce: bool,
+    pub cast: bool,
+    pub flatten: bool,
+    pub path: Option<LitStr>,
+    pub skip: bool,
+    pub with: Option<syn::Path>,
+    pub convert: Option<Convert>,
+    pub bound: Option<Bound>,
+    pub index: Option<usize>,
+    /// Settings for writing the column to a file
+    pub compression: Option<LitStr>,
+    pub encoding: Option<LitStr>,
+    pub dictionary: Option<bool>,
+    pub bloom_filter: Option<bool>,
+}
+
+impl FieldAttrs {
+    /// Parses the attributes of a field of type `ty`
+    pub fn from_ast(attrs: &[Attribute], ty: &Type) -> syn::Result<Self> {
+        let mut out = FieldAttrs::default();
+        // The attributes given, for error spans
+        let mut seen = Vec::<Ident>::new();
+        for attr in attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("arrow_struct"))
+        {
+            attr.parse_nested_meta(|meta| {
+                seen.extend(meta.path.get_ident().cloned());
+                if meta.path.is_ident("on_null") {
+                    set_once(&meta, &mut out.on_null, OnNull::parse(&meta)?)
+                } else if meta.path.is_ident("default") {
+                    let default = if meta.input.peek(syn::Token![=]) {
+                        let path: LitStr = meta.value()?.parse()?;
+                        FieldDefault::Path(path.parse()?)
+                    } else {
+                        FieldDefault::Trait
+                    };
+                    set_once(&meta, &mut out.default, default)
+                } else if meta.path.is_ident("alias") {
+                    out.aliases.push(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("coerce") {
+                    if out.cast {
+                        return Err(meta.error("`coerce` can't be combined with `cast`"));
+                    }
+                    out.coerce = true;
+                    Ok(())
+                } else if meta.path.is_ident("path") {
+                    let path: LitStr = meta.value()?.parse()?;
+                    if path.value().split('.').any(str::is_empty) {
+                        return Err(syn::Error::new(
+                            path.span(),
+                            "expected column names separated by `.`",
+                        ));
+                    }
+                    set_once(&meta, &mut out.path, path)
+                } else if meta.path.is_ident("with") {
+                    let path: LitStr = meta.value()?.parse()?;
+                    set_once(&meta, &mut out.with, path.parse()?)
+                } else if meta.path.is_ident("from") {
+                    let ty: LitStr = meta.value()?.parse()?;
+                    set_once(&meta, &mut out.convert, Convert::From(ty.parse()?))
+                } else if meta.path.is_ident("try_from") {
+                    let ty: LitStr = meta.value()?.parse()?;
+                    set_once(&meta, &mut out.convert, Convert::TryFrom(ty.parse()?))
+                } else if meta.path.is_ident("parse") {
+                    set_once(&meta, &mut out.convert, Convert::Parse)
+                } else if meta.path.is_ident("bound") {
+                    set_once(&meta, &mut out.bound, parse_bound(&meta)?)
+                } else if meta.path.is_ident("index") {
+                    let index: LitInt = meta.value()?.parse()?;
+                    set_once(&meta, &mut out.index, index.base10_parse()?)
+                } else if meta.path.is_ident("skip") {
+                    out.skip = true;
+                    Ok(())
+                } else if meta.path.is_ident("flatten") {
+                    out.flatten = true;
+                    Ok(())
+                } else if meta.path.is_ident("cast") {
+                    if out.coerce {
+                        return Err(meta.error("`cast` can't be combined with `coerce`"));
+                    }
+                    out.cast = true;
+                    Ok(())
+                } else if meta.path.is_ident("compression") {
+                    set_once(&meta, &mut out.compression, meta.value()?.parse()?)
+                } else if meta.path.is_ident("encoding") {
+                    set_once(&meta, &mut out.encoding, meta.value()?.parse()?)
+                } else if meta.path.is_ident("dictionary") {
+                    set_once(&meta, &mut out.dictionary, parse_flag(&meta)?)
+                } else if meta.path.is_ident("bloom_filter") {
+                    set_once(&meta, &mut out.bloom_filter, parse_flag(&meta)?)
+                } else {
+                    Err(meta.error("unknown arrow_struct field attribute"))
+                }
+            })?;
+        }
+
+        // Conflicts are reported at the attribute that can't be combined with the others
+        let error = |name: &str, message: &str| {
+            let ident = seen.iter().find(|ident| *ident == name).unwrap();
+            Err(syn::Error::new(ident.span(), message))
+        };
+        let convert = ["from", "try_from", "parse"]
+            .into_iter()
+            .find(|name| seen.iter().any(|ident| ident == name));
+        if out.on_null.is_some() && crate::is_option(ty) {
+            return error("on_null", "`on_null` has no effect on `Option` fields");
+        }
+        if out.path.is_some() && !out.aliases.is_empty() {
+            return error("alias", "`alias` can't be combined with `path`");
+        }
+        if out.index.is_some() && (out.path.is_some() || !out.aliases.is_empty()) {
+            return error("index", "`index` can't be combined with `path` or `alias`");
+        }
+        if out.with.is_some() && (out.coerce || out.cast || convert.is_some()) {
+            return error(
+                "with",
+                "`with` can't be combined with `coerce`, `cast`, `from`, `try_from` or `parse`",
+            );
+        }
+        if let Some(convert) = convert.filter(|_| out.default.is_some()) {
+            return error(
+                convert,
+                &format!("`{convert}` can't be combined with `default`"),
+            );
+        }
+        if out.flatten
+            && seen
+                .iter()
+                .any(|ident| ident != "flatten" && ident != "bound")
+        {
+            return error(
+                "flatten",
+                "`flatten` can't be combined with other arrow_struct attributes",
+            );
+        }
+        if out.skip
+            && seen
+                .iter()
+                .any(|ident| ident != "skip" && ident != "default" && ident != "bound")
+        {
+            return error("skip", "`skip` can only be combined with `default`");
+        }
+        Ok(out)
+    }
+}
+
+/// `name` or `name = bool`
+fn parse_flag(meta: &ParseNestedMeta) -> syn::Result<bool> {
+    if meta.input.peek(Token![=]) {
+        let value: LitBool = meta.value()?.parse()?;
+        Ok(value.value)
+    } else {
+        Ok(true)
+    }
+}
+
+fn set_once<T>(meta: &ParseNestedMeta, slot: &mut Option<T>, value: T) -> syn::Result<()> {
+    if slot.is_some() {
+        return Err(meta.error("duplicate arrow_struct attribute"));
+    }
+    *slot = Some(value);
+    Ok(())
+}
@@ -1,17 +1,23 @@
 extern crate proc_macro;
 
-use convert_case::{Case, Casing};
-use proc_macro2::{Span, TokenStream};
-use quote::{format_ident, quote, quote_spanned};
+mod attr;
+mod serialize;
+
+use attr::{ContainerAttrs, Convert, FieldAttrs, FieldDefault, OnNull};
+use proc_macro2::{Group, Punct, Spacing, Span, TokenStream, TokenTree};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
 use syn::spanned::Spanned;
-use syn::{parse_macro_input, Data, DeriveInput, Fields, GenericParam, LifetimeParam};
+use syn::{
+    parse_macro_input, Data, DeriveInput, Fields, GenericArgument, GenericParam, Generics,
+    LifetimeParam, PathArguments, Type, WherePredicate,
+};
 
-#[proc_macro_derive(Deserialize)]
+#[proc_macro_derive(Deserialize, attributes(arrow_struct))]
 pub fn derive_deserialize(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
     let name = input.ident;
-    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
 
     // We add our reserved lifetime parameter 'ar (like 'de of serde::Deserialize) and add all existing lifetimes as bounds
     let lt = syn::Lifetime::new("'ar", Span::call_site());
@@ -21,15 +27,54 @@ pub fn derive_deserialize(input: proc_macro::TokenStream) -> proc_macro::TokenSt
     }
     let mut new_generics = input.generics.clone();
     new_generics.params.push(GenericParam::Lifetime(ltp));
-    let (impl_generics, _, _) = new_generics.split_for_impl();
 
-    let inner = inner_implementation(&input.data);
+    let container = match ContainerAttrs::from_ast(&input.attrs) {
+        Ok(container) => container,
+        Err(error) => return error.to_compile_error().into(),
+    };
+    let (inner, columns, bounds, skips_rows) =
+        match inner_implementation(&input.data, &input.generics, &container) {
+            Ok(inner) => inner,
+            Err(error) => return error.to_compile_error().into(),
+        };
+    new_generics.make_where_clause().predicates.extend(bounds);
+    let (impl_generics, _, where_clause) = new_generics.split_for_impl();
+    let deny_unknown_columns = container.deny_unknown_columns;
+    let krate = &container.krate;
+    let normalize_name = match (&container.normalize, container.case_insensitive) {
+        (None, false) => quote! {},
+        (normalize, case_insensitive) => {
+            let mut name = match normalize {
+                Some(normalize) => quote! { #normalize(name) },
+                None => quote! { name },
+            };
+            if case_insensitive {
+                name = quote! { #name.to_lowercase() };
+            }
+            quote! {
+                fn normalize_name(name: &str) -> std::borrow::Cow<'_, str> {
+                    std::borrow::Cow::Owned(#name)
+                }
+            }
+        }
+    };
 
     let expanded = quote! {
-        impl #impl_generics arrow_struct::FromArrayRef<'ar> for #name #ty_generics #where_clause {
-            fn from_array_ref(array: &'ar arrow_struct::ArrayRef) -> impl Iterator<Item=Self> {
-                let array = arrow_struct::AsArray::as_struct(array);
+        impl #impl_generics #krate::FromStructArray<'ar> for #name #ty_generics #where_clause {
+            const DENY_UNKNOWN_COLUMNS: bool = #deny_unknown_columns;
+            const SKIPS_ROWS: bool = #skips_rows;
 
+            fn columns() -> Vec<#krate::Column> {
+                let mut columns = Vec::new();
+                #columns
+                columns
+            }
+
+            #normalize_name
+
+            fn try_from_columns(
+                array: &'ar #krate::StructArray,
+            ) -> #krate::Result<impl Iterator<Item = #krate::Result<Self>> + Send> {
                 #inner
             }
         }
@@ -38,51 +83,596 @@ pub fn derive_deserialize(input: proc_macro::TokenStream) -> proc_macro::TokenSt
     proc_macro::TokenStream::from(expanded)
 }
 
-fn inner_implementation(data: &Data) -> TokenStream {
+#[proc_macro_derive(Serialize, attributes(arrow_struct))]
+pub fn derive_serialize(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match serialize::derive(&input) {
+        Ok(expanded) => expanded.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Returns the body of `try_from_columns`, the statements collecting `columns`, the bounds the
+/// fields need, and whether rows can be skipped
+fn inner_implementation(
+    data: &Data,
+    generics: &Generics,
+    container: &ContainerAttrs,
+) -> syn::Result<(TokenStream, TokenStream, Vec<WherePredicate>, bool)> {
     match *data {
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
                 let idents = fields.named.iter().map(|f| f.ident.clone());
-                let idents_clone = idents.clone();
-
-                let iterators = fields.named.iter().map(|field| {
-                    let name = field.ident.as_ref().unwrap().to_string();
-                    let field_type = field.ty.clone();
-                    let column_name = name.clone(); //.to_case(Case::Camel);
-                    let iterator_name = format_ident!("__arrow_struct_derive_{}", name);
-
-                    let iterator_declaration = quote_spanned! {field.span()=>
-                        let mut #iterator_name = {
-                            let array = array.column_by_name(#column_name)
-                                             .expect(stringify!(no column named #column_name));
-                            <#field_type as arrow_struct::FromArrayRef>::from_array_ref(array)
-                        };
-                    };
-                    (iterator_name, iterator_declaration)
+                let values = fields
+                    .named
+                    .iter()
+                    .map(|f| format_ident!("__arrow_struct_value_{}", f.ident.as_ref().unwrap()));
+                let values_clone = values.clone();
+
+                // With `by_position`, fields without an explicit index are read from the column
+                // at their position, not counting skipped fields
+                let mut position = 0;
+                let iterators = fields
+                    .named
+                    .iter()
+                    .map(|field| {
+                        let mut attrs = FieldAttrs::from_ast(&field.attrs, &field.ty)?;
+                        if container.by_position && !attrs.skip {
+                            if attrs.flatten || attrs.path.is_some() || !attrs.aliases.is_empty() {
+                                return Err(syn::Error::new_spanned(
+                                    field,
+                                    "`flatten`, `path` and `alias` can't be used with `by_position`",
+                                ));
+                            }
+                            attrs.index.get_or_insert(position);
+                            position += 1;
+                        }
+                        Ok((field, attrs))
+                    })
+                    .collect::<syn::Result<Vec<_>>>()?;
+
+                // Like serde, bounds are inferred for the fields using type parameters, unless
+                // they are given explicitly for the field or the whole struct
+                let type_params = generics
+                    .type_params()
+                    .map(|param| &param.ident)
+                    .collect::<Vec<_>>();
+                let mut bounds = Vec::new();
+                for (field, attrs) in &iterators {
+                    match (&container.bound, &attrs.bound) {
+                        (Some(_), _) => {}
+                        (None, Some(bound)) => bounds.extend(bound.iter().cloned()),
+                        (None, None) if mentions(field.ty.to_token_stream(), &type_params) => {
+                            bounds.extend(field_bounds(field, attrs, container, &type_params)?)
+                        }
+                        (None, None) => {}
+                    }
+                }
+                if let Some(bound) = &container.bound {
+                    bounds.extend(bound.iter().cloned());
+                }
+                let skips_rows = iterators.iter().any(|(field, attrs)| {
+                    !attrs.skip
+                        && !attrs.flatten
+                        && matches!(on_null(field, attrs, container), Some(OnNull::Skip))
                 });
 
-                let iterator_declarations = iterators.clone().map(|(_, declaration)| declaration);
-                let iterator_next = iterators.clone().map(|(name, _)| quote! { #name.next() });
+                let iterators = iterators
+                    .iter()
+                    .map(|(field, attrs)| field_implementation(field, attrs, container))
+                    .collect::<Vec<_>>();
 
-                quote! {
+                let iterator_declarations = iterators.iter().map(|field| &field.declaration);
+                let iterator_next = iterators.iter().map(|field| {
+                    let name = &field.iterator_name;
+                    quote! { #name.next() }
+                });
+                let conversions = iterators.iter().map(|field| &field.conversion);
+
+                let columns = iterators.iter().map(|field| &field.columns);
+
+                let inner = quote! {
                     #(#iterator_declarations)*
 
-                    std::iter::from_fn(move || {
-                        if let (#(Some(#idents)),*) = (#(#iterator_next),*) {
-                            Some(Self { #(#idents_clone),* })
-                        } else {
-                            None
-                        }
-                    })
-                }
+                    let mut __arrow_struct_row = 0usize;
+                    Ok(std::iter::from_fn(move || loop {
+                        let (#(Some(#values),)*) = (#(#iterator_next,)*) else {
+                            return None;
+                        };
+                        let __arrow_struct_row_index = __arrow_struct_row;
+                        __arrow_struct_row += 1;
+                        #(#conversions)*
+                        return Some(Ok(Self { #(#idents: #values_clone),* }));
+                    }))
+                };
+                Ok((inner, quote! { #(#columns)* }, bounds, skips_rows))
+            }
+            Fields::Unnamed(ref fields) => Err(syn::Error::new_spanned(
+                fields,
+                "Deserialize can only be derived for structs with named fields",
+            )),
+            Fields::Unit => Err(syn::Error::new_spanned(
+                data.struct_token,
+                "Deserialize can only be derived for structs with named fields",
+            )),
+        },
+        Data::Enum(ref data) => Err(syn::Error::new_spanned(
+            data.enum_token,
+            "Deserialize can only be derived for structs, not enums",
+        )),
+        Data::Union(ref data) => Err(syn::Error::new_spanned(
+            data.union_token,
+            "Deserialize can only be derived for structs, not unions",
+        )),
+    }
+}
+
+struct FieldImplementation {
+    iterator_name: syn::Ident,
+    /// Adds the columns read by the field to `columns`
+    columns: TokenStream,
+    /// Declares the iterator over the field's column
+    declaration: TokenStream,
+    /// Turns the item of the iterator into the field value, or returns from the row
+    conversion: TokenStream,
+}
+
+fn field_implementation(
+    field: &syn::Field,
+    attrs: &FieldAttrs,
+    container: &ContainerAttrs,
+) -> FieldImplementation {
+    let krate = &container.krate;
+    let ident = field.ident.as_ref().unwrap();
+    let name = ident.to_string();
+    let field_type = &field.ty;
+    let column_name = name.clone(); //.to_case(Case::Camel);
+    let iterator_name = format_ident!("__arrow_struct_derive_{}", name);
+    // The values are bound to prefixed names, so they don't shadow e.g. default functions
+    let value_name = format_ident!("__arrow_struct_value_{}", name);
+
+    if attrs.skip {
+        let default = match &attrs.default {
+            None | Some(FieldDefault::Trait) => quote! { <#field_type as Default>::default() },
+            Some(FieldDefault::Path(path)) => quote! { #path() },
+        };
+        return FieldImplementation {
+            declaration: quote_spanned! {field.span()=>
+                let mut #iterator_name = (0..#krate::Array::len(array)).map(|_| ());
+            },
+            conversion: quote_spanned! {field.span()=>
+                let #value_name = #default;
+            },
+            columns: quote! {},
+            iterator_name,
+        };
+    }
+
+    if attrs.flatten {
+        return FieldImplementation {
+            declaration: quote_spanned! {field.span()=>
+                #krate::__private::deny_skipped_rows::<#field_type>(
+                    <#field_type as #krate::FromStructArray>::SKIPS_ROWS,
+                )?;
+                let mut #iterator_name =
+                    <#field_type as #krate::FromStructArray>::try_from_columns(array)?;
+            },
+            conversion: quote! {
+                let #value_name = match #value_name {
+                    Ok(value) => value,
+                    Err(error) => return Some(Err(error)),
+                };
+            },
+            columns: quote_spanned! {field.span()=>
+                columns.extend(<#field_type as #krate::FromStructArray>::columns());
+            },
+            iterator_name,
+        };
+    }
+
+    let on_null = on_null(field, attrs, container);
+    // Cast columns are read as `Option`, as only some types have non-`Option` implementations
+    let cast_as_option = attrs.cast && !is_option(field_type);
+    // With a conversion, the column is read as `Option` of the type converted from
+    let decoded_type = match (&attrs.convert, on_null) {
+        (Some(Convert::From(ty) | Convert::TryFrom(ty)), _) => quote! { Option<#ty> },
+        (Some(Convert::Parse), _) => quote! { Option<&str> },
+        (None, Some(_)) => quote! { Option<#field_type> },
+        (None, None) if cast_as_option => quote! { Option<#field_type> },
+        (None, None) => quote! { #field_type },
+    };
+
+    let aliases = &attrs.aliases;
+    // With a path, the field is read from a nested column, and also null if any of its parents is
+    let (column_name, prelude, lookup, names, parent_nulls) = match &attrs.path {
+        // Columns read by position are named by their index in errors
+        None if attrs.index.is_some() => {
+            let index = attrs.index.unwrap();
+            let column_name = format!("#{index}");
+            (
+                column_name.clone(),
+                quote! {},
+                quote! {
+                    (#index < array.num_columns()).then(|| array.column(#index))
+                },
+                quote! { &[#column_name] },
+                None,
+            )
+        }
+        None if container.normalize.is_some() || container.case_insensitive => (
+            column_name.clone(),
+            quote! {},
+            quote! {
+                #krate::__private::column_by_normalized_name(
+                    array,
+                    &[#column_name, #(#aliases),*],
+                    Self::normalize_name,
+                )?
+            },
+            quote! { &[#column_name, #(#aliases),*] },
+            None,
+        ),
+        None => (
+            column_name.clone(),
+            quote! {},
+            quote! {
+                [#column_name, #(#aliases),*]
+                    .into_iter()
+                    .find_map(|name| array.column_by_name(name))
+            },
+            quote! { &[#column_name, #(#aliases),*] },
+            None,
+        ),
+        Some(path) => {
+            let path = path.value();
+            let segments = path.split('.');
+            let root = path.split('.').next().unwrap().to_string();
+            let column = format_ident!("__arrow_struct_column_{}", name);
+            let nulls = format_ident!("__arrow_struct_nulls_{}", name);
+            let prelude = quote! {
+                let (#column, #nulls) =
+                    #krate::__private::column_by_path(array, &[#(#segments),*])?.unzip();
+                let #nulls = #nulls.flatten();
+            };
+            (
+                path,
+                prelude,
+                quote! { #column },
+                quote! { &[#root] },
+                Some(nulls),
+            )
+        }
+    };
+
+    let decode = if let Some(with) = &attrs.with {
+        quote! { #with::try_from_array_ref(column) }
+    } else if attrs.cast {
+        quote! { #krate::__private::cast::<#decoded_type>(column) }
+    } else if attrs.coerce || container.coerce {
+        quote! { <#decoded_type as #krate::FromArrayRef>::coerce_from_array_ref(column) }
+    } else {
+        quote! { <#decoded_type as #krate::FromArrayRef>::try_from_array_ref(column) }
+    };
+    let decode = quote! {
+        #decode.map_err(|error| error.in_column(#column_name))?
+    };
+    // Values of nested columns are matched up with their rows by position
+    let prelude = match &attrs.with {
+        Some(_) => prelude,
+        None => quote! {
+            #prelude
+            #krate::__private::deny_skipped_rows::<#decoded_type>(
+                <#decoded_type as #krate::FromArrayRef>::SKIPS_ROWS,
+            )
+            .map_err(|error| error.in_column(#column_name))?;
+        },
+    };
+    let declaration = match &attrs.default {
+        None => quote_spanned! {field.span()=>
+            #prelude
+            let mut #iterator_name = {
+                let column = #lookup
+                    .ok_or_else(|| #krate::Error::missing_column(#column_name))?;
+                #decode
+            };
+        },
+        Some(default) => {
+            let default = match default {
+                FieldDefault::Trait => quote! { <#field_type as Default>::default() },
+                FieldDefault::Path(path) => quote! { #path() },
+            };
+            // The default is decoded too, so wrapped in `Option` when the column is read as one
+            let default = if on_null.is_some() || cast_as_option {
+                quote! { Some(#default) }
+            } else {
+                default
+            };
+            quote_spanned! {field.span()=>
+                #prelude
+                let mut #iterator_name = {
+                    match #lookup {
+                        Some(column) => #krate::__private::Either::Left(#decode),
+                        None => #krate::__private::Either::Right(
+                            std::iter::repeat_with(|| Ok(#default))
+                                .take(#krate::Array::len(array)),
+                        ),
+                    }
+                };
             }
-            Fields::Unnamed(_) => {
-                unimplemented!()
+        }
+    };
+
+    let on_none = match on_null {
+        None => None,
+        Some(OnNull::Error) => Some(quote! {
+            return Some(Err(#krate::Error::UnexpectedNull { row: __arrow_struct_row_index }.in_column(#column_name)))
+        }),
+        Some(OnNull::Default) => Some(quote! { Default::default() }),
+        Some(OnNull::Skip) => Some(quote! { continue }),
+    };
+    let unexpected_null = quote! {
+        return Some(Err(#krate::Error::UnexpectedNull { row: __arrow_struct_row_index }.in_column(#column_name)))
+    };
+    let value = match (&attrs.convert, &on_none) {
+        (None, None) if cast_as_option => quote! {
+            match value {
+                Some(value) => value,
+                None => #unexpected_null,
             }
-            Fields::Unit => {
-                unimplemented!()
+        },
+        (None, None) => quote! { value },
+        (None, Some(on_none)) => quote! {
+            match value {
+                Some(value) => value,
+                None => #on_none,
             }
         },
-        Data::Enum(_) | Data::Union(_) => unimplemented!(),
+        (Some(convert), on_none) => {
+            let value_type = option_inner(field_type).unwrap_or(field_type);
+            let conversion_error = quote! {
+                return Some(Err(#krate::Error::conversion(__arrow_struct_row_index, error).in_column(#column_name)))
+            };
+            let converted = match convert {
+                Convert::From(ty) => quote! { value.map(<#value_type as From<#ty>>::from) },
+                Convert::TryFrom(ty) => quote! {
+                    match value.map(<#value_type as TryFrom<#ty>>::try_from).transpose() {
+                        Ok(value) => value,
+                        Err(error) => #conversion_error,
+                    }
+                },
+                Convert::Parse => quote! {
+                    match value.map(str::parse::<#value_type>).transpose() {
+                        Ok(value) => value,
+                        Err(error) => #conversion_error,
+                    }
+                },
+            };
+            if is_option(field_type) {
+                converted
+            } else {
+                let on_none = on_none.as_ref().unwrap_or(&unexpected_null);
+                quote! {
+                    match #converted {
+                        Some(value) => value,
+                        None => #on_none,
+                    }
+                }
+            }
+        }
+    };
+    let mut conversion = quote! {
+        match #value_name {
+            Ok(value) => #value,
+            Err(error) => return Some(Err(error.in_column(#column_name))),
+        }
+    };
+    if let Some(nulls) = parent_nulls {
+        let on_parent_null = match on_none {
+            Some(on_none) => on_none,
+            None if is_option(field_type) => quote! { None },
+            None => unexpected_null,
+        };
+        conversion = quote! {
+            if #nulls.as_ref().is_some_and(|nulls| nulls.is_null(__arrow_struct_row_index)) {
+                #on_parent_null
+            } else {
+                #conversion
+            }
+        };
+    }
+    let conversion = quote! {
+        let #value_name = #conversion;
+    };
+
+    let required = attrs.default.is_none();
+    let exact_name = attrs.path.is_some();
+    let index = match attrs.index {
+        Some(index) => quote! { Some(#index) },
+        None => quote! { None },
+    };
+    let mut column_type = if attrs.with.is_some() {
+        quote! { || #krate::ColumnType::Unknown }
+    } else if attrs.cast {
+        quote! { || #krate::ColumnType::Native(<#decoded_type as #krate::ArrowField>::data_type()) }
+    } else {
+        quote! { <#decoded_type as #krate::FromArrayRef>::column_type }
+    };
+    // The parents of a path are structs, of which only the next segment is read
+    if let Some(path) = &attrs.path {
+        for segment in path
+            .value()
+            .split('.')
+            .skip(1)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+        {
+            column_type = quote! {
+                || #krate::ColumnType::struct_of(vec![#krate::Column {
+                    names: &[#segment],
+                    index: None,
+                    exact_name: true,
+                    required: #required,
+                    column_type: #column_type,
+                }])
+            };
+        }
+    }
+    let columns = quote! {
+        columns.push(#krate::Column {
+            names: #names,
+            index: #index,
+            exact_name: #exact_name,
+            required: #required,
+            column_type: #column_type,
+        });
+    };
+
+    FieldImplementation {
+        iterator_name,
+        columns,
+        declaration,
+        conversion,
+    }
+}
+
+/// The null policy of the field
+fn on_null(field: &syn::Field, attrs: &FieldAttrs, container: &ContainerAttrs) -> Option<OnNull> {
+    // The container's null policy only applies to fields which can't represent null themselves
+    attrs
+        .on_null
+        .or_else(|| container.on_null.filter(|_| !is_option(&field.ty)))
+}
+
+/// The bounds needed to read the field
+fn field_bounds(
+    field: &syn::Field,
+    attrs: &FieldAttrs,
+    container: &ContainerAttrs,
+    type_params: &[&syn::Ident],
+) -> syn::Result<Vec<WherePredicate>> {
+    let krate = &container.krate;
+    let field_type = &field.ty;
+    let decoded_type = match on_null(field, attrs, container) {
+        _ if attrs.cast && !is_option(field_type) => quote! { Option<#field_type> },
+        Some(_) => quote! { Option<#field_type> },
+        None => quote! { #field_type },
+    };
+    let mut bounds = Vec::new();
+    if (matches!(attrs.default, None | Some(FieldDefault::Trait))
+        && (attrs.skip || attrs.default.is_some()))
+        || matches!(on_null(field, attrs, container), Some(OnNull::Default))
+    {
+        bounds.push(quote! { #field_type: Default });
+    }
+    if attrs.skip || attrs.with.is_some() {
+        // Nothing is known about custom decoders
+    } else if attrs.flatten {
+        bounds.push(quote! { #field_type: #krate::FromStructArray<'ar> });
+    } else if let Some(convert) = &attrs.convert {
+        let value_type = option_inner(field_type).unwrap_or(field_type);
+        if let Convert::From(ty) | Convert::TryFrom(ty) = convert {
+            if mentions(ty.to_token_stream(), type_params) {
+                let ty = with_ar_lifetime(ty.to_token_stream());
+                bounds.push(quote! { Option<#ty>: #krate::FromArrayRef<'ar> });
+            }
+        }
+        match convert {
+            Convert::From(ty) => {
+                let ty = with_ar_lifetime(ty.to_token_stream());
+                bounds.push(quote! { #value_type: From<#ty> })
+            }
+            Convert::TryFrom(ty) => {
+                let ty = with_ar_lifetime(ty.to_token_stream());
+                bounds.extend([
+                    quote! { #value_type: TryFrom<#ty> },
+                    quote! { <#value_type as TryFrom<#ty>>::Error: std::fmt::Display },
+                ])
+            }
+            Convert::Parse => bounds.extend([
+                quote! { #value_type: std::str::FromStr },
+                quote! { <#value_type as std::str::FromStr>::Err: std::fmt::Display },
+            ]),
+        }
+    } else if attrs.cast {
+        bounds.push(quote! {
+            #decoded_type: for<'b> #krate::FromArrayRef<'b> + #krate::ArrowField
+        });
+    } else {
+        bounds.push(quote! { #decoded_type: #krate::FromArrayRef<'ar> });
+    }
+    bounds.into_iter().map(syn::parse2).collect()
+}
+
+/// Whether any of the identifiers appears in the tokens, e.g. a type parameter in a type
+fn mentions(tokens: TokenStream, idents: &[&syn::Ident]) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => idents.contains(&&ident),
+        TokenTree::Group(group) => mentions(group.stream(), idents),
+        _ => false,
+    })
+}
+
+/// The tokens with elided lifetimes of references (`&T` and `'_`) replaced by `'ar`, as bounds
+/// can't elide them
+fn with_ar_lifetime(tokens: TokenStream) -> TokenStream {
+    let mut out = TokenStream::new();
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Group(group) => {
+                let mut replaced = Group::new(group.delimiter(), with_ar_lifetime(group.stream()));
+                replaced.set_span(group.span());
+                out.extend([TokenTree::Group(replaced)]);
+            }
+            TokenTree::Punct(punct) if punct.as_char() == '&' => {
+                let mut ampersand = Punct::new('&', Spacing::Alone);
+                ampersand.set_span(punct.span());
+                out.extend([TokenTree::Punct(ampersand)]);
+                if !matches!(tokens.peek(), Some(TokenTree::Punct(next)) if next.as_char() == '\'')
+                {
+                    out.extend(quote! { 'ar });
+                }
+            }
+            TokenTree::Punct(punct)
+                if punct.as_char() == '\''
+                    && matches!(tokens.peek(), Some(TokenTree::Ident(ident)) if ident == "_") =>
+            {
+                tokens.next();
+                out.extend(quote! { 'ar });
+            }
+            token => out.extend([token]),
+        }
+    }
+    out
+}
+
+/// The type inside an `Option` (syntactically)
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    match arguments.args.first()? {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    }
+}
+
+/// Whether the type is (syntactically) an `Option`
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
     }
 }
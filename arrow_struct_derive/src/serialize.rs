@@ -0,0 +1,166 @@
+//! `#[derive(Serialize)]`, writing structs to arrays
+use crate::attr::{ContainerAttrs, FieldAttrs};
+use crate::mentions;
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{Data, DeriveInput, Fields};
+
+pub(crate) fn derive(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let container = ContainerAttrs::from_ast(&input.attrs)?;
+    let krate = &container.krate;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields,
+            Fields::Unnamed(fields) => {
+                return Err(syn::Error::new_spanned(
+                    fields,
+                    "Serialize can only be derived for structs with named fields",
+                ))
+            }
+            Fields::Unit => {
+                return Err(syn::Error::new_spanned(
+                    data.struct_token,
+                    "Serialize can only be derived for structs with named fields",
+                ))
+            }
+        },
+        Data::Enum(data) => {
+            return Err(syn::Error::new_spanned(
+                data.enum_token,
+                "Serialize can only be derived for structs, not enums",
+            ))
+        }
+        Data::Union(data) => {
+            return Err(syn::Error::new_spanned(
+                data.union_token,
+                "Serialize can only be derived for structs, not unions",
+            ))
+        }
+    };
+
+    let type_params = input
+        .generics
+        .type_params()
+        .map(|param| &param.ident)
+        .collect::<Vec<_>>();
+    let mut bounds = Vec::new();
+    let mut arrow_fields = Vec::new();
+    let mut columns = Vec::new();
+    let mut column_options = Vec::new();
+    for field in &fields.named {
+        let attrs = FieldAttrs::from_ast(&field.attrs, &field.ty)?;
+        if attrs.skip {
+            continue;
+        }
+        if attrs.path.is_some() || attrs.with.is_some() || attrs.convert.is_some() {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`path`, `with`, `from`, `try_from` and `parse` are not supported by Serialize",
+            ));
+        }
+        let ident = field.ident.as_ref().unwrap();
+        let name = ident.to_string();
+        let ty = &field.ty;
+        let values = quote! { values.clone().map(|value| value.map(|value| &value.#ident)) };
+
+        if attrs.flatten {
+            if mentions(ty.to_token_stream(), &type_params) {
+                bounds.push(quote! { #ty: #krate::ToStructArray });
+            }
+            arrow_fields.push(quote! {
+                fields.extend(<#ty as #krate::ToStructArray>::fields().iter().cloned());
+            });
+            columns.push(quote! {
+                columns.extend(<#ty as #krate::ToStructArray>::to_columns(#values));
+            });
+            column_options.push(quote! {
+                options.extend(<#ty as #krate::ToStructArray>::column_options());
+            });
+            continue;
+        }
+
+        if mentions(ty.to_token_stream(), &type_params) {
+            bounds.push(quote! { #ty: #krate::ToArrayRef });
+        }
+        arrow_fields.push(quote! {
+            fields.push(std::sync::Arc::new(#krate::__private::arrow::datatypes::Field::new(
+                #name,
+                <#ty as #krate::ArrowField>::data_type(),
+                <#ty as #krate::ArrowField>::NULLABLE,
+            )));
+        });
+        columns.push(quote! {
+            columns.push(<#ty as #krate::ToArrayRef>::to_array_ref(#values));
+        });
+        if attrs.compression.is_some()
+            || attrs.encoding.is_some()
+            || attrs.dictionary.is_some()
+            || attrs.bloom_filter.is_some()
+        {
+            let compression = option(&attrs.compression);
+            let encoding = option(&attrs.encoding);
+            let dictionary = option(&attrs.dictionary);
+            let bloom_filter = option(&attrs.bloom_filter);
+            column_options.push(quote! {
+                options.push(#krate::ColumnOptions {
+                    path: vec![#name],
+                    compression: #compression,
+                    encoding: #encoding,
+                    dictionary: #dictionary,
+                    bloom_filter: #bloom_filter,
+                });
+            });
+        }
+        // The options of nested columns override those of the field
+        column_options.push(quote! {
+            for mut nested in <#ty as #krate::ToArrayRef>::column_options() {
+                nested.path.insert(0, #name);
+                options.push(nested);
+            }
+        });
+    }
+
+    let name = &input.ident;
+    let mut generics = input.generics.clone();
+    generics.make_where_clause().predicates.extend(
+        bounds
+            .into_iter()
+            .map(syn::parse2::<syn::WherePredicate>)
+            .collect::<syn::Result<Vec<_>>>()?,
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #krate::ToStructArray for #name #ty_generics #where_clause {
+            fn fields() -> #krate::Fields {
+                let mut fields = Vec::<#krate::__private::arrow::datatypes::FieldRef>::new();
+                #(#arrow_fields)*
+                #krate::Fields::from(fields)
+            }
+
+            fn to_columns<'__b>(
+                values: impl Iterator<Item = Option<&'__b Self>> + Clone,
+            ) -> Vec<#krate::ArrayRef>
+            where
+                Self: '__b,
+            {
+                let mut columns = Vec::<#krate::ArrayRef>::new();
+                #(#columns)*
+                columns
+            }
+
+            fn column_options() -> Vec<#krate::ColumnOptions> {
+                let mut options = Vec::<#krate::ColumnOptions>::new();
+                #(#column_options)*
+                options
+            }
+        }
+    })
+}
+
+/// An `Option` expression of the value
+fn option(value: &Option<impl ToTokens>) -> TokenStream {
+    match value {
+        Some(value) => quote! { Some(#value) },
+        None => quote! { None },
+    }
+}
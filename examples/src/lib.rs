@@ -1,14 +1,22 @@
 #[cfg(test)]
 mod tests {
     use arrow::array::{
-        Array, BinaryArray, GenericListBuilder, Int32Builder, LargeBinaryArray, LargeStringArray,
-        RecordBatch, StructArray,
+        Array, ArrayRef, AsArray, BinaryArray, Float32Array, GenericListBuilder, Int32Array,
+        Int32Builder, Int64Array, LargeBinaryArray, LargeStringArray, ListArray, RecordBatch,
+        StructArray,
     };
-    use arrow::datatypes::{DataType, Field, FieldRef, Schema};
-    use arrow_struct::Deserialize;
-    use arrow_struct::FromArrayRef;
+    use arrow::buffer::NullBuffer;
+    use arrow::datatypes::{DataType, Field, FieldRef, Int64Type, Schema};
+    use arrow::ipc::writer::{FileWriter, StreamWriter};
+    use arrow_struct::{Deserialize, Serialize};
+    use arrow_struct::{FromArrayOwned, FromArrayRef};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use parquet::arrow::ArrowWriter;
+    use parquet::basic::{Compression, Encoding};
+    use parquet::file::properties::WriterProperties;
     use serde_arrow::_impl::arrow::array::StringArray;
     use serde_arrow::schema::{SchemaLike, TracingOptions};
+    use std::io::Cursor;
     use std::sync::Arc;
 
     #[allow(dead_code)]
@@ -44,7 +52,7 @@ mod tests {
                 f32: Some(9.0 + i as f32),
                 f64: Some(10.0 + i as f64),
                 str: Some(&some_string[..i as usize]),
-                byte_slice: Some(some_string[..i as usize].as_bytes()),
+                byte_slice: Some(&some_string.as_bytes()[..i as usize]),
             })
             .collect::<Vec<_>>();
         let fields =
@@ -227,4 +235,1822 @@ mod tests {
             SmallAndLargeArrays::from_array_ref(&array).collect::<Vec<_>>()
         );
     }
+
+    fn nullable_ints() -> ArrayRef {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("value", DataType::Int64, true),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3])),
+                Arc::new(Int64Array::from(vec![Some(10), None, Some(30)])),
+            ],
+        )
+        .unwrap();
+        let struct_array: StructArray = batch.into();
+        Arc::new(struct_array)
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct DefaultOnNull {
+        id: i64,
+        #[arrow_struct(on_null = "default")]
+        value: i64,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[arrow_struct(on_null = "error")]
+    struct ErrorOnNull {
+        id: i64,
+        value: i64,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[arrow_struct(skip_null_rows)]
+    struct SkipNullRows {
+        id: i64,
+        value: i64,
+    }
+
+    #[test]
+    fn null_policies() {
+        let array = nullable_ints();
+        assert_eq!(
+            DefaultOnNull::from_array_ref(&array).collect::<Vec<_>>(),
+            vec![
+                DefaultOnNull { id: 1, value: 10 },
+                DefaultOnNull { id: 2, value: 0 },
+                DefaultOnNull { id: 3, value: 30 },
+            ]
+        );
+        assert_eq!(
+            SkipNullRows::from_array_ref(&array).collect::<Vec<_>>(),
+            vec![
+                SkipNullRows { id: 1, value: 10 },
+                SkipNullRows { id: 3, value: 30 },
+            ]
+        );
+        let error = ErrorOnNull::try_from_array_ref(&array)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_err();
+        assert_eq!(error.to_string(), "column value: unexpected null at row 1");
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct WrongType {
+            value: Option<String>,
+        }
+        let error = WrongType::try_from_array_ref(&array).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "column value: Expected String, was Int64"
+        );
+    }
+
+    #[test]
+    fn skipped_rows_are_only_skipped_at_the_outermost_level() {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Nested {
+            id: i64,
+            inner: SkipNullRows,
+        }
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Flattened {
+            #[arrow_struct(flatten)]
+            inner: SkipNullRows,
+        }
+
+        // Skipping rows of the inner struct would shift them against the `id`s
+        let inner = nullable_ints();
+        let outer = StructArray::from(vec![
+            (
+                Arc::new(Field::new("id", DataType::Int64, false)),
+                Arc::new(Int64Array::from(vec![10, 20, 30])) as ArrayRef,
+            ),
+            (
+                Arc::new(Field::new("inner", inner.data_type().clone(), false)),
+                inner.clone(),
+            ),
+        ]);
+        let outer = Arc::new(outer) as ArrayRef;
+        let error = Nested::try_from_array_ref(&outer).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "column inner: examples::tests::SkipNullRows skips rows, so it can only be read at the outermost level"
+        );
+        let error = Flattened::try_from_array_ref(&inner).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "examples::tests::SkipNullRows skips rows, so it can only be read at the outermost level"
+        );
+        let error = Option::<SkipNullRows>::try_from_array_ref(&inner)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "examples::tests::SkipNullRows skips rows, so it can only be read at the outermost level"
+        );
+    }
+
+    #[test]
+    fn errors_in_lists() {
+        let array: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+            Some(vec![None, Some(2), Some(3)]),
+            Some(vec![Some(4)]),
+        ]));
+        let rows = Option::<Vec<i64>>::try_from_array_ref(&array)
+            .unwrap()
+            .collect::<Vec<_>>();
+        // The error is at the row of the list, and the rest of its values are skipped
+        assert_eq!(
+            rows[0].as_ref().unwrap_err().to_string(),
+            "unexpected null at row 0"
+        );
+        assert_eq!(rows[1].as_ref().unwrap(), &Some(vec![4]));
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct OptionalInner {
+        inner: Option<Inner>,
+    }
+
+    #[test]
+    fn null_struct_is_none() {
+        let inner = StructArray::new(
+            vec![Field::new("i64", DataType::Int64, true)].into(),
+            vec![Arc::new(Int64Array::from(vec![Some(1), None])) as ArrayRef],
+            Some(NullBuffer::from(vec![true, false])),
+        );
+        let outer = StructArray::new(
+            vec![Field::new("inner", inner.data_type().clone(), true)].into(),
+            vec![Arc::new(inner) as ArrayRef],
+            None,
+        );
+        let array = Arc::new(outer) as ArrayRef;
+        assert_eq!(
+            OptionalInner::from_array_ref(&array).collect::<Vec<_>>(),
+            vec![
+                OptionalInner {
+                    inner: Some(Inner { i64: Some(1) })
+                },
+                OptionalInner { inner: None },
+            ]
+        );
+    }
+
+    fn default_label() -> Option<String> {
+        Some("unknown".to_string())
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Evolved {
+        #[arrow_struct(alias = "value")]
+        amount: Option<i64>,
+        #[arrow_struct(default)]
+        added: Option<i64>,
+        #[arrow_struct(default)]
+        count: u32,
+        #[arrow_struct(default = "default_label")]
+        label: Option<String>,
+    }
+
+    #[test]
+    fn missing_columns() {
+        let array = nullable_ints();
+        assert_eq!(
+            Evolved::from_array_ref(&array).collect::<Vec<_>>(),
+            [Some(10), None, Some(30)]
+                .into_iter()
+                .map(|amount| Evolved {
+                    amount,
+                    added: None,
+                    count: 0,
+                    label: Some("unknown".to_string()),
+                })
+                .collect::<Vec<_>>()
+        );
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Missing {
+            missing: Option<i64>,
+        }
+        let error = Missing::try_from_array_ref(&array).err().unwrap();
+        assert_eq!(error.to_string(), "missing columns: missing");
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[arrow_struct(deny_unknown_columns)]
+    #[allow(dead_code)]
+    struct Strict {
+        #[arrow_struct(alias = "identifier")]
+        id: i64,
+        #[arrow_struct(default)]
+        added: Option<i64>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[arrow_struct(deny_unknown_columns)]
+    #[allow(dead_code)]
+    struct StrictMissing {
+        id: i64,
+        missing: Option<i64>,
+        also_missing: Option<i64>,
+    }
+
+    #[test]
+    fn deny_unknown_columns() {
+        let array = nullable_ints();
+        let error = Strict::try_from_array_ref(&array).err().unwrap();
+        assert_eq!(error.to_string(), "unknown columns: value");
+
+        let error = StrictMissing::try_from_array_ref(&array).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "missing columns: missing, also_missing; unknown columns: value"
+        );
+
+        #[derive(Debug, Deserialize)]
+        #[arrow_struct(deny_unknown_columns)]
+        #[allow(dead_code)]
+        struct AllKnown {
+            id: i64,
+            value: Option<i64>,
+        }
+        assert_eq!(AllKnown::from_array_ref(&array).count(), 3);
+    }
+
+    fn narrow_columns() -> ArrayRef {
+        let mut builder: GenericListBuilder<i32, Int32Builder> =
+            GenericListBuilder::new(Int32Builder::new());
+        builder.values().append_value(1);
+        builder.append(true);
+        builder.append(false);
+        builder.values().append_value(300);
+        builder.append(true);
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "int",
+                Arc::new(Int32Array::from(vec![Some(1), None, Some(300)])) as ArrayRef,
+            ),
+            (
+                "float",
+                Arc::new(Float32Array::from(vec![0.5, 1.5, 2.5])) as ArrayRef,
+            ),
+            ("list", Arc::new(builder.finish()) as ArrayRef),
+        ])
+        .unwrap();
+        let struct_array: StructArray = batch.into();
+        Arc::new(struct_array)
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[arrow_struct(coerce)]
+    struct Widened {
+        int: Option<i64>,
+        float: f64,
+        list: Option<Vec<i64>>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Decimal {
+        #[arrow_struct(coerce)]
+        int: Option<i128>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Narrowed {
+        #[arrow_struct(coerce)]
+        int: Option<i8>,
+    }
+
+    #[test]
+    fn coerce() {
+        let array = narrow_columns();
+        assert_eq!(
+            Widened::from_array_ref(&array).collect::<Vec<_>>(),
+            vec![
+                Widened {
+                    int: Some(1),
+                    float: 0.5,
+                    list: Some(vec![1]),
+                },
+                Widened {
+                    int: None,
+                    float: 1.5,
+                    list: None,
+                },
+                Widened {
+                    int: Some(300),
+                    float: 2.5,
+                    list: Some(vec![300]),
+                },
+            ]
+        );
+        assert_eq!(
+            Decimal::from_array_ref(&array).collect::<Vec<_>>(),
+            vec![
+                Decimal { int: Some(1) },
+                Decimal { int: None },
+                Decimal { int: Some(300) }
+            ]
+        );
+
+        let rows = Narrowed::try_from_array_ref(&array)
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(rows[0].as_ref().unwrap(), &Narrowed { int: Some(1) });
+        assert_eq!(rows[1].as_ref().unwrap(), &Narrowed { int: None });
+        assert_eq!(
+            rows[2].as_ref().unwrap_err().to_string(),
+            "column int: value at row 2 does not fit into Int8 (from Int32)"
+        );
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct NotCoerced {
+            int: Option<i64>,
+        }
+        let error = NotCoerced::try_from_array_ref(&array).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "column int: Expected Int64Type, was Int32"
+        );
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Cast {
+        #[arrow_struct(cast)]
+        id: Option<String>,
+        #[arrow_struct(cast)]
+        value: Option<f64>,
+    }
+
+    #[test]
+    fn cast() {
+        let array = nullable_ints();
+        assert_eq!(
+            Cast::from_array_ref(&array).collect::<Vec<_>>(),
+            vec![
+                Cast {
+                    id: Some("1".to_string()),
+                    value: Some(10.0)
+                },
+                Cast {
+                    id: Some("2".to_string()),
+                    value: None
+                },
+                Cast {
+                    id: Some("3".to_string()),
+                    value: Some(30.0)
+                },
+            ]
+        );
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Parsed {
+            #[arrow_struct(cast)]
+            string: Option<i64>,
+        }
+        let batch = RecordBatch::try_from_iter(vec![(
+            "string",
+            Arc::new(StringArray::from(vec!["1", "x"])) as ArrayRef,
+        )])
+        .unwrap();
+        let array = Arc::new(StructArray::from(batch)) as ArrayRef;
+        let error = Parsed::try_from_array_ref(&array).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "column string: Cast error: Cannot cast string 'x' to value of Int64 type"
+        );
+
+        // Types without a non-`Option` implementation, like `String`
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Required {
+            #[arrow_struct(cast)]
+            id: String,
+            #[arrow_struct(cast)]
+            value: bool,
+            #[arrow_struct(cast, default)]
+            missing: String,
+        }
+        let array = nullable_ints();
+        let rows = Required::try_from_array_ref(&array)
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows[0].as_ref().unwrap(),
+            &Required {
+                id: "1".to_string(),
+                value: true,
+                missing: String::new(),
+            }
+        );
+        assert_eq!(
+            rows[1].as_ref().unwrap_err().to_string(),
+            "column value: unexpected null at row 1"
+        );
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Common {
+        id: i64,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[arrow_struct(deny_unknown_columns)]
+    struct Flattened {
+        #[arrow_struct(flatten)]
+        common: Common,
+        value: Option<i64>,
+    }
+
+    #[test]
+    fn flatten() {
+        let array = nullable_ints();
+        assert_eq!(
+            Flattened::from_array_ref(&array).collect::<Vec<_>>(),
+            vec![
+                Flattened {
+                    common: Common { id: 1 },
+                    value: Some(10)
+                },
+                Flattened {
+                    common: Common { id: 2 },
+                    value: None
+                },
+                Flattened {
+                    common: Common { id: 3 },
+                    value: Some(30)
+                },
+            ]
+        );
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct MissingInFlattened {
+            #[arrow_struct(flatten)]
+            flattened: Flattened,
+            missing: Option<i64>,
+        }
+        let error = MissingInFlattened::try_from_array_ref(&array)
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "missing columns: missing");
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Paths {
+        #[arrow_struct(path = "receipt.gas.used")]
+        gas_used: Option<i64>,
+        #[arrow_struct(path = "receipt.gas.used", on_null = "default")]
+        gas_used_or_zero: i64,
+        #[arrow_struct(path = "receipt.missing", default)]
+        missing: Option<i64>,
+    }
+
+    #[test]
+    fn paths() {
+        let gas = StructArray::new(
+            vec![Field::new("used", DataType::Int64, true)].into(),
+            vec![Arc::new(Int64Array::from(vec![Some(1), Some(2), None])) as ArrayRef],
+            None,
+        );
+        let receipt = StructArray::new(
+            vec![Field::new("gas", gas.data_type().clone(), true)].into(),
+            vec![Arc::new(gas) as ArrayRef],
+            Some(NullBuffer::from(vec![true, false, true])),
+        );
+        let array = Arc::new(StructArray::new(
+            vec![Field::new("receipt", receipt.data_type().clone(), true)].into(),
+            vec![Arc::new(receipt) as ArrayRef],
+            None,
+        )) as ArrayRef;
+        assert_eq!(
+            Paths::from_array_ref(&array).collect::<Vec<_>>(),
+            vec![
+                Paths {
+                    gas_used: Some(1),
+                    gas_used_or_zero: 1,
+                    missing: None
+                },
+                Paths {
+                    gas_used: None,
+                    gas_used_or_zero: 0,
+                    missing: None
+                },
+                Paths {
+                    gas_used: None,
+                    gas_used_or_zero: 0,
+                    missing: None
+                },
+            ]
+        );
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct NotAStruct {
+            #[arrow_struct(path = "receipt.gas.used.value")]
+            value: Option<i64>,
+        }
+        let error = NotAStruct::try_from_array_ref(&array).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "column receipt.gas.used: Expected Struct, was Int64"
+        );
+    }
+
+    fn chain_id() -> u64 {
+        1
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct WithContext {
+        id: i64,
+        #[arrow_struct(skip)]
+        source: Option<String>,
+        #[arrow_struct(skip, default = "chain_id")]
+        chain_id: u64,
+    }
+
+    #[test]
+    fn skip() {
+        let array = nullable_ints();
+        assert_eq!(
+            WithContext::from_array_ref(&array).collect::<Vec<_>>(),
+            (1..=3)
+                .map(|id| WithContext {
+                    id,
+                    source: None,
+                    chain_id: 1
+                })
+                .collect::<Vec<_>>()
+        );
+    }
+
+    mod hex {
+        use arrow::array::ArrayRef;
+        use arrow_struct::{Error, FromArrayRef};
+
+        pub fn try_from_array_ref<'a>(
+            array: &'a ArrayRef,
+        ) -> arrow_struct::Result<impl Iterator<Item = arrow_struct::Result<Option<Vec<u8>>>> + 'a>
+        {
+            Ok(
+                <Option<&str> as FromArrayRef>::try_from_array_ref(array)?.map(|value| {
+                    let Some(value) = value? else {
+                        return Ok(None);
+                    };
+                    let digits = value.strip_prefix("0x").unwrap_or(value);
+                    (0..digits.len())
+                        .step_by(2)
+                        .map(|i| {
+                            digits
+                                .get(i..i + 2)
+                                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                                .ok_or_else(|| Error::custom(format!("invalid hex: {value}")))
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                        .map(Some)
+                }),
+            )
+        }
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Hex {
+        #[arrow_struct(with = "hex")]
+        hash: Option<Vec<u8>>,
+    }
+
+    #[test]
+    fn with() {
+        let batch = RecordBatch::try_from_iter(vec![(
+            "hash",
+            Arc::new(StringArray::from(vec![Some("0x01ff"), None, Some("0x1")])) as ArrayRef,
+        )])
+        .unwrap();
+        let array = Arc::new(StructArray::from(batch)) as ArrayRef;
+        let rows = Hex::try_from_array_ref(&array).unwrap().collect::<Vec<_>>();
+        assert_eq!(
+            rows[0].as_ref().unwrap(),
+            &Hex {
+                hash: Some(vec![1, 255])
+            }
+        );
+        assert_eq!(rows[1].as_ref().unwrap(), &Hex { hash: None });
+        assert_eq!(
+            rows[2].as_ref().unwrap_err().to_string(),
+            "column hash: invalid hex: 0x1"
+        );
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct BlockNumber(i64);
+
+    impl From<i64> for BlockNumber {
+        fn from(value: i64) -> Self {
+            BlockNumber(value)
+        }
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Converted {
+        #[arrow_struct(from = "i64")]
+        id: BlockNumber,
+        #[arrow_struct(try_from = "i64")]
+        value: Option<u8>,
+        #[arrow_struct(parse)]
+        amount: f64,
+    }
+
+    #[test]
+    fn conversions() {
+        let batch = RecordBatch::try_from_iter(vec![
+            ("id", Arc::new(Int64Array::from(vec![1, 2, 3])) as ArrayRef),
+            (
+                "value",
+                Arc::new(Int64Array::from(vec![Some(10), None, Some(300)])) as ArrayRef,
+            ),
+            (
+                "amount",
+                Arc::new(StringArray::from(vec!["1.5", "2", "3"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let array = Arc::new(StructArray::from(batch)) as ArrayRef;
+        let rows = Converted::try_from_array_ref(&array)
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows[0].as_ref().unwrap(),
+            &Converted {
+                id: BlockNumber(1),
+                value: Some(10),
+                amount: 1.5
+            }
+        );
+        assert_eq!(
+            rows[1].as_ref().unwrap(),
+            &Converted {
+                id: BlockNumber(2),
+                value: None,
+                amount: 2.0
+            }
+        );
+        assert_eq!(
+            rows[2].as_ref().unwrap_err().to_string(),
+            "column value: could not convert value at row 2: out of range integral type conversion attempted"
+        );
+
+        #[derive(Debug, Deserialize)]
+        struct Parsed {
+            #[arrow_struct(parse)]
+            #[allow(dead_code)]
+            value: u32,
+        }
+        let batch = RecordBatch::try_from_iter(vec![(
+            "value",
+            Arc::new(StringArray::from(vec![Some("1"), Some("x"), None])) as ArrayRef,
+        )])
+        .unwrap();
+        let array = Arc::new(StructArray::from(batch)) as ArrayRef;
+        let errors = Parsed::try_from_array_ref(&array)
+            .unwrap()
+            .map(|row| row.err().map(|error| error.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                None,
+                Some(
+                    "column value: could not convert value at row 1: invalid digit found in string"
+                        .to_string()
+                ),
+                Some("column value: unexpected null at row 2".to_string()),
+            ]
+        );
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Page<T> {
+        id: i64,
+        items: Option<Vec<T>>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[arrow_struct(bound = "T: arrow_struct::FromStructArray<'ar>")]
+    struct Wrapper<T> {
+        #[arrow_struct(flatten)]
+        inner: T,
+    }
+
+    #[test]
+    fn generics() {
+        let batch = RecordBatch::try_from_iter(vec![
+            ("id", Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef),
+            (
+                "items",
+                Arc::new(ListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+                    Some(vec![Some(1), None]),
+                    None,
+                ])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let array = Arc::new(StructArray::from(batch)) as ArrayRef;
+        assert_eq!(
+            Page::<Option<i64>>::from_array_ref(&array).collect::<Vec<_>>(),
+            vec![
+                Page {
+                    id: 1,
+                    items: Some(vec![Some(1), None])
+                },
+                Page { id: 2, items: None }
+            ]
+        );
+
+        let array = nullable_ints();
+        assert_eq!(
+            Wrapper::<DefaultOnNull>::from_array_ref(&array)
+                .map(|wrapper| wrapper.inner.value)
+                .collect::<Vec<_>>(),
+            vec![10, 0, 30]
+        );
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct GenericConversions<T, U, V, W, X> {
+        #[arrow_struct(on_null = "default")]
+        value: T,
+        #[arrow_struct(try_from = "i64")]
+        id: U,
+        #[arrow_struct(parse)]
+        name: Option<V>,
+        #[arrow_struct(try_from = "&str", alias = "name")]
+        label: Option<W>,
+        #[arrow_struct(from = "T", alias = "value")]
+        wide_value: Option<X>,
+    }
+
+    /// A non-empty string
+    #[derive(Debug, PartialEq)]
+    struct Label(String);
+
+    impl TryFrom<&str> for Label {
+        type Error = &'static str;
+
+        fn try_from(value: &str) -> Result<Self, Self::Error> {
+            match value {
+                "" => Err("empty label"),
+                value => Ok(Label(value.to_string())),
+            }
+        }
+    }
+
+    #[test]
+    fn generic_conversions() {
+        let batch = RecordBatch::try_from_iter(vec![
+            ("id", Arc::new(Int64Array::from(vec![1, 300])) as ArrayRef),
+            (
+                "value",
+                Arc::new(Int64Array::from(vec![None, Some(20)])) as ArrayRef,
+            ),
+            (
+                "name",
+                Arc::new(StringArray::from(vec![Some("1.5"), None])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let array = Arc::new(StructArray::from(batch)) as ArrayRef;
+        let rows = GenericConversions::<i64, u8, f64, Label, i128>::try_from_array_ref(&array)
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows[0].as_ref().unwrap(),
+            &GenericConversions {
+                value: 0,
+                id: 1,
+                name: Some(1.5),
+                label: Some(Label("1.5".to_string())),
+                wide_value: None,
+            }
+        );
+        assert_eq!(
+            rows[1].as_ref().unwrap_err().to_string(),
+            "column id: could not convert value at row 1: out of range integral type conversion attempted"
+        );
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[arrow_struct(by_position)]
+    struct Positional {
+        id: i64,
+        value: Option<i64>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Indexed {
+        #[arrow_struct(index = 1)]
+        value: Option<i64>,
+        #[arrow_struct(index = 2, default)]
+        missing: Option<i64>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TooManyColumns {
+        #[arrow_struct(index = 2)]
+        #[allow(dead_code)]
+        value: Option<i64>,
+    }
+
+    #[test]
+    fn positions() {
+        // Empty and duplicate names can only be read by position
+        let batch = RecordBatch::try_from_iter(vec![
+            ("", Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef),
+            (
+                "",
+                Arc::new(Int64Array::from(vec![Some(10), None])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let array = Arc::new(StructArray::from(batch)) as ArrayRef;
+        assert_eq!(
+            Positional::from_array_ref(&array).collect::<Vec<_>>(),
+            vec![
+                Positional {
+                    id: 1,
+                    value: Some(10)
+                },
+                Positional { id: 2, value: None }
+            ]
+        );
+        assert_eq!(
+            Indexed::from_array_ref(&array).collect::<Vec<_>>(),
+            vec![
+                Indexed {
+                    value: Some(10),
+                    missing: None
+                },
+                Indexed {
+                    value: None,
+                    missing: None
+                }
+            ]
+        );
+        let error = TooManyColumns::try_from_array_ref(&array).err().unwrap();
+        assert_eq!(error.to_string(), "missing columns: #2");
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[arrow_struct(match_case = "insensitive")]
+    struct CaseInsensitive {
+        id: i64,
+        value: Option<i64>,
+    }
+
+    fn alphanumeric(name: &str) -> String {
+        name.chars().filter(|c| c.is_alphanumeric()).collect()
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[arrow_struct(
+        match_case = "insensitive",
+        normalize = "alphanumeric",
+        deny_unknown_columns
+    )]
+    struct Normalized {
+        block_number: i64,
+        value: Option<i64>,
+    }
+
+    #[test]
+    fn normalized_names() {
+        let columns = |id: &str, value: &str| {
+            let batch = RecordBatch::try_from_iter(vec![
+                (id, Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef),
+                (
+                    value,
+                    Arc::new(Int64Array::from(vec![Some(10), None])) as ArrayRef,
+                ),
+            ])
+            .unwrap();
+            Arc::new(StructArray::from(batch)) as ArrayRef
+        };
+
+        let array = columns("ID", "Value");
+        assert_eq!(
+            CaseInsensitive::from_array_ref(&array).collect::<Vec<_>>(),
+            vec![
+                CaseInsensitive {
+                    id: 1,
+                    value: Some(10)
+                },
+                CaseInsensitive { id: 2, value: None }
+            ]
+        );
+
+        let array = columns("Block-Number", "\"value\"");
+        assert_eq!(
+            Normalized::from_array_ref(&array).collect::<Vec<_>>(),
+            vec![
+                Normalized {
+                    block_number: 1,
+                    value: Some(10)
+                },
+                Normalized {
+                    block_number: 2,
+                    value: None
+                }
+            ]
+        );
+
+        let array = columns("id", "ID");
+        #[derive(Debug, Deserialize)]
+        #[arrow_struct(match_case = "insensitive")]
+        struct Id {
+            #[allow(dead_code)]
+            id: i64,
+        }
+        let error = Id::try_from_array_ref(&array).err().unwrap();
+        assert_eq!(error.to_string(), "id matches more than one column: id, ID");
+
+        // Names in paths are matched exactly
+        #[derive(Debug, Deserialize)]
+        #[arrow_struct(match_case = "insensitive")]
+        struct GasUsed {
+            #[arrow_struct(path = "receipt.used")]
+            #[allow(dead_code)]
+            used: i64,
+        }
+        let receipt = StructArray::from(vec![(
+            Arc::new(Field::new("used", DataType::Int64, false)),
+            Arc::new(Int64Array::from(vec![1])) as ArrayRef,
+        )]);
+        let receipts = |name: &str| {
+            StructArray::from(vec![(
+                Arc::new(Field::new(name, receipt.data_type().clone(), false)),
+                Arc::new(receipt.clone()) as ArrayRef,
+            )])
+        };
+        let error = <GasUsed as arrow_struct::FromStructArray>::validate_fields(
+            receipts("Receipt").fields(),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), "missing columns: receipt");
+        assert!(<GasUsed as arrow_struct::FromStructArray>::validate_fields(
+            receipts("receipt").fields()
+        )
+        .is_ok());
+    }
+
+    fn parquet_file() -> bytes::Bytes {
+        let batch = RecordBatch::from(nullable_ints().as_struct());
+        let mut file = Vec::new();
+        let mut writer = ArrowWriter::try_new(&mut file, batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        file.into()
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Value {
+        value: Option<i64>,
+    }
+
+    #[test]
+    fn parquet() {
+        let file = parquet_file();
+        let rows = arrow_struct::parquet::read::<DefaultOnNull>(file.clone())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                DefaultOnNull { id: 1, value: 10 },
+                DefaultOnNull { id: 2, value: 0 },
+                DefaultOnNull { id: 3, value: 30 },
+            ]
+        );
+
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file.clone()).unwrap();
+        let mask =
+            arrow_struct::parquet::projection::<Value>(builder.schema(), builder.parquet_schema());
+        assert!(!mask.leaf_included(0));
+        assert!(mask.leaf_included(1));
+        let values = arrow_struct::parquet::read::<Value>(file)
+            .unwrap()
+            .map(|row| row.unwrap().value)
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![Some(10), None, Some(30)]);
+    }
+
+    #[derive(Serialize)]
+    struct Gas {
+        used: i64,
+        limit: i64,
+    }
+
+    #[derive(Serialize)]
+    struct Receipt {
+        gas: Gas,
+        status: bool,
+    }
+
+    #[derive(Serialize)]
+    struct Tag {
+        name: String,
+        weight: i64,
+    }
+
+    #[derive(Serialize)]
+    struct Transaction {
+        id: i64,
+        receipt: Receipt,
+        tags: Vec<Tag>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct TagName {
+        name: Option<String>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct GasUsedAndTags {
+        #[arrow_struct(path = "receipt.gas.used")]
+        gas_used: i64,
+        tags: Option<Vec<TagName>>,
+    }
+
+    #[test]
+    fn parquet_nested_projection() {
+        let transactions = (1..=2)
+            .map(|id| Transaction {
+                id,
+                receipt: Receipt {
+                    gas: Gas {
+                        used: id * 10,
+                        limit: 100,
+                    },
+                    status: true,
+                },
+                tags: vec![Tag {
+                    name: format!("tag{id}"),
+                    weight: id,
+                }],
+            })
+            .collect::<Vec<_>>();
+        let batch = arrow_struct::ToStructArray::to_record_batch(&transactions);
+        let mut file = Vec::new();
+        let mut writer = ArrowWriter::try_new(&mut file, batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        let file = bytes::Bytes::from(file);
+
+        // The leaves are id, receipt.gas.used, receipt.gas.limit, receipt.status, tags.name and
+        // tags.weight
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file.clone()).unwrap();
+        let mask = arrow_struct::parquet::projection::<GasUsedAndTags>(
+            builder.schema(),
+            builder.parquet_schema(),
+        );
+        let included = (0..6)
+            .filter(|leaf| mask.leaf_included(*leaf))
+            .collect::<Vec<_>>();
+        assert_eq!(included, vec![1, 4]);
+
+        let rows = arrow_struct::parquet::read::<GasUsedAndTags>(file)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            (1..=2)
+                .map(|id| GasUsedAndTags {
+                    gas_used: id * 10,
+                    tags: Some(vec![TagName {
+                        name: Some(format!("tag{id}"))
+                    }]),
+                })
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Id {
+        id: i64,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct StringId {
+        #[allow(dead_code)]
+        id: Option<String>,
+    }
+
+    #[test]
+    fn parquet_filter() {
+        let file = parquet_file();
+        let values =
+            arrow_struct::parquet::read_filtered::<Value, Id>(file.clone(), |key| key.id >= 2)
+                .unwrap()
+                .map(|row| row.unwrap().value)
+                .collect::<Vec<_>>();
+        assert_eq!(values, vec![None, Some(30)]);
+
+        let error = arrow_struct::parquet::read_filtered::<Value, StringId>(file.clone(), |_| true)
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "column id: Expected String, was Int64");
+        let error = arrow_struct::parquet::read_filtered::<Value, SkipNullRows>(file, |_| true)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "examples::tests::SkipNullRows skips rows, so it can only be read at the outermost level"
+        );
+    }
+
+    #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+    struct Transfer {
+        #[arrow_struct(on_null = "error", compression = "zstd(3)", bloom_filter)]
+        hash: String,
+        #[arrow_struct(encoding = "delta_binary_packed", dictionary = false)]
+        block_number: i64,
+        amount: Option<i128>,
+        topics: Option<Vec<i64>>,
+        #[arrow_struct(compression = "snappy")]
+        sender: Option<Account>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+    struct Account {
+        #[arrow_struct(compression = "gzip(6)")]
+        address: Option<String>,
+        nonce: Option<u64>,
+    }
+
+    #[test]
+    fn parquet_writer() {
+        let transfers = (0..10_000)
+            .map(|i| Transfer {
+                hash: format!("0x{i:x}"),
+                block_number: i,
+                amount: (i % 3 != 0).then_some(i as i128 * 1_000_000_000_000),
+                topics: (i % 5 != 0).then(|| vec![i, i + 1]),
+                sender: (i % 7 != 0).then(|| Account {
+                    address: Some(format!("0x{:x}", i % 10)),
+                    nonce: None,
+                }),
+            })
+            .collect::<Vec<_>>();
+        let mut file = Vec::new();
+        let properties = WriterProperties::builder().set_max_row_group_size(4_000);
+        let mut writer =
+            arrow_struct::parquet::Writer::try_new_with_properties(&mut file, properties).unwrap();
+        for transfer in &transfers {
+            writer.write(transfer.clone()).unwrap();
+        }
+        writer.close().unwrap();
+        let file = bytes::Bytes::from(file);
+
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file.clone()).unwrap();
+        let metadata = builder.metadata();
+        assert_eq!(metadata.num_row_groups(), 3);
+        let columns = metadata.row_group(0).columns();
+        let column = |path: &str| {
+            columns
+                .iter()
+                .find(|column| column.column_path().string() == path)
+                .unwrap()
+        };
+        // Compression levels aren't stored in the file
+        let hash = column("hash");
+        assert!(matches!(hash.compression(), Compression::ZSTD(_)));
+        assert!(hash.bloom_filter_offset().is_some());
+        let block_number = column("block_number");
+        assert!(block_number
+            .encodings()
+            .contains(&Encoding::DELTA_BINARY_PACKED));
+        assert!(block_number.dictionary_page_offset().is_none());
+        assert!(column("amount").bloom_filter_offset().is_none());
+        assert_eq!(column("sender.nonce").compression(), Compression::SNAPPY);
+        assert!(matches!(
+            column("sender.address").compression(),
+            Compression::GZIP(_)
+        ));
+
+        let rows = arrow_struct::parquet::read::<Transfer>(file)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rows, transfers);
+
+        #[derive(Serialize)]
+        struct DictionaryEncoded {
+            #[arrow_struct(encoding = "rle_dictionary")]
+            #[allow(dead_code)]
+            id: i64,
+        }
+        let error = arrow_struct::parquet::Writer::<DictionaryEncoded, _>::try_new(Vec::new())
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "column id: RLE_DICTIONARY can't be set as the encoding, use `dictionary` instead"
+        );
+    }
+
+    #[test]
+    fn ipc() {
+        let batch = RecordBatch::from(nullable_ints().as_struct());
+        let expected = vec![
+            DefaultOnNull { id: 1, value: 10 },
+            DefaultOnNull { id: 2, value: 0 },
+            DefaultOnNull { id: 3, value: 30 },
+        ];
+
+        let mut file = Vec::new();
+        let mut writer = FileWriter::try_new(&mut file, &batch.schema()).unwrap();
+        writer.write(&batch).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+        let rows = arrow_struct::ipc::FileReader::<DefaultOnNull, _>::try_new(Cursor::new(&file))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rows.len(), 6);
+        assert_eq!(rows[..3], expected);
+        assert_eq!(rows[3..], expected);
+
+        let mut stream = Vec::new();
+        let mut writer = StreamWriter::try_new(&mut stream, &batch.schema()).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+        let rows = arrow_struct::ipc::StreamReader::<DefaultOnNull, _>::try_new(stream.as_slice())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rows, expected);
+
+        // The schema is checked before reading any rows
+        let error = arrow_struct::ipc::StreamReader::<Transfer, _>::try_new(stream.as_slice())
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "missing columns: hash, block_number, amount, topics, sender"
+        );
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Borrowed<'a> {
+        id: i64,
+        name: Option<&'a str>,
+        data: Option<&'a [u8]>,
+    }
+
+    #[test]
+    fn mmap() {
+        let batch = RecordBatch::try_from_iter(vec![
+            ("id", Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef),
+            (
+                "name",
+                Arc::new(StringArray::from(vec![Some("one"), None])) as ArrayRef,
+            ),
+            (
+                "data",
+                Arc::new(BinaryArray::from(vec![None, Some(b"two".as_slice())])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let path =
+            std::env::temp_dir().join(format!("arrow_struct_mmap_{}.arrow", std::process::id()));
+        let mut writer =
+            FileWriter::try_new(std::fs::File::create(&path).unwrap(), &batch.schema()).unwrap();
+        writer.write(&batch).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+
+        // Safety: the file isn't modified until it's removed below
+        let file = unsafe { arrow_struct::ipc::MmapFile::open(&path) }.unwrap();
+        assert_eq!(file.num_rows(), 4);
+        let rows = file
+            .rows::<Borrowed>()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let expected = [
+            Borrowed {
+                id: 1,
+                name: Some("one"),
+                data: None,
+            },
+            Borrowed {
+                id: 2,
+                name: None,
+                data: Some(b"two"),
+            },
+        ];
+        assert_eq!(rows[..2], expected);
+        assert_eq!(rows[2..], expected);
+
+        let error = file.rows::<Transfer>().err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "missing columns: hash, block_number, amount, topics, sender"
+        );
+        drop(file);
+        std::fs::remove_file(&path).unwrap();
+
+        // A file missing the data of its batches, but not the footer pointing to them
+        let batch = RecordBatch::try_from_iter(vec![(
+            "id",
+            Arc::new(Int64Array::from_iter_values(0..100_000)) as ArrayRef,
+        )])
+        .unwrap();
+        let mut file = Vec::new();
+        let mut writer = FileWriter::try_new(&mut file, &batch.schema()).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+        let footer_len = i32::from_le_bytes(file[file.len() - 10..][..4].try_into().unwrap());
+        let footer_start = file.len() - 10 - footer_len as usize;
+        let truncated = [&file[..8], &file[footer_start..]].concat();
+        let error =
+            arrow_struct::ipc::MmapFile::from_buffer(arrow::buffer::Buffer::from_vec(truncated))
+                .err()
+                .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Ipc error: invalid IPC file: block out of bounds"
+        );
+    }
+
+    #[test]
+    fn ipc_batch_writer() {
+        let accounts = (0..2_500)
+            .map(|i| Account {
+                address: (i % 3 != 0).then(|| format!("0x{i:x}")),
+                nonce: Some(i),
+            })
+            .collect::<Vec<_>>();
+
+        let mut writer = arrow_struct::ipc::BatchWriter::try_new_stream(Vec::new())
+            .unwrap()
+            .with_max_rows(1_000);
+        for account in &accounts {
+            writer.write(account.clone()).unwrap();
+        }
+        let stream = writer.finish().unwrap();
+        let batches = arrow::ipc::reader::StreamReader::try_new(stream.as_slice(), None)
+            .unwrap()
+            .map(|batch| batch.unwrap().num_rows())
+            .collect::<Vec<_>>();
+        assert_eq!(batches, [1_000, 1_000, 500]);
+        let rows = arrow_struct::ipc::StreamReader::<Account, _>::try_new(stream.as_slice())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rows, accounts);
+
+        // Every row is larger than the threshold
+        let mut writer = arrow_struct::ipc::BatchWriter::try_new_file(Vec::new())
+            .unwrap()
+            .with_max_bytes(1);
+        for account in &accounts {
+            writer.write(account.clone()).unwrap();
+        }
+        let file = writer.finish().unwrap();
+        let reader = arrow::ipc::reader::FileReader::try_new(Cursor::new(&file), None).unwrap();
+        assert_eq!(reader.num_batches(), 2_500);
+        let rows = arrow_struct::ipc::FileReader::<Account, _>::try_new(Cursor::new(&file))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rows, accounts);
+
+        // Lowering the limit below the buffered rows writes them with the next row
+        let mut writer = arrow_struct::ipc::BatchWriter::try_new_stream(Vec::new()).unwrap();
+        for account in &accounts[..1_024] {
+            writer.write(account.clone()).unwrap();
+        }
+        let mut writer = writer.with_max_rows(100);
+        for account in &accounts[1_024..1_274] {
+            writer.write(account.clone()).unwrap();
+        }
+        let stream = writer.finish().unwrap();
+        let batches = arrow::ipc::reader::StreamReader::try_new(stream.as_slice(), None)
+            .unwrap()
+            .map(|batch| batch.unwrap().num_rows())
+            .collect::<Vec<_>>();
+        assert_eq!(batches, [1_025, 100, 100, 49]);
+
+        // Rows of about 10KB are written about 10 to a batch, not in chunks of 1024
+        let large = (0..100)
+            .map(|i| Account {
+                address: Some(format!("{i:0>10000}")),
+                nonce: Some(i),
+            })
+            .collect::<Vec<_>>();
+        let mut writer = arrow_struct::ipc::BatchWriter::try_new_stream(Vec::new())
+            .unwrap()
+            .with_max_bytes(100_000);
+        for account in &large {
+            writer.write(account.clone()).unwrap();
+        }
+        let stream = writer.finish().unwrap();
+        let batches = arrow::ipc::reader::StreamReader::try_new(stream.as_slice(), None)
+            .unwrap()
+            .map(|batch| batch.unwrap().num_rows())
+            .collect::<Vec<_>>();
+        assert_eq!(batches.iter().sum::<usize>(), 100);
+        assert!(batches.iter().all(|rows| *rows <= 11), "{batches:?}");
+        let rows = arrow_struct::ipc::StreamReader::<Account, _>::try_new(stream.as_slice())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rows, large);
+    }
+
+    /// Can only be read, as `parse` fields can't be written
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Renamed {
+        #[arrow_struct(alias = "addr")]
+        address: Option<String>,
+        #[arrow_struct(alias = "n")]
+        nonce: Option<u64>,
+        #[arrow_struct(parse)]
+        amount: Option<u128>,
+    }
+
+    #[test]
+    fn csv() {
+        // Columns are found by name, and empty values are null
+        let csv = "nonce,extra,address\n1,a,0x1\n2,b,\n";
+        let rows = arrow_struct::csv::read::<Account>(csv.as_bytes())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let expected = [
+            Account {
+                address: Some("0x1".to_string()),
+                nonce: Some(1),
+            },
+            Account {
+                address: None,
+                nonce: Some(2),
+            },
+        ];
+        assert_eq!(rows, expected);
+
+        // Columns are found by alias or position too, and parsed as the types they're read as
+        let csv = "n,addr,amount\n1,0x1,12\n";
+        let rows = arrow_struct::csv::read::<Renamed>(csv.as_bytes())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            [Renamed {
+                address: Some("0x1".to_string()),
+                nonce: Some(1),
+                amount: Some(12),
+            }]
+        );
+        let csv = "a,b\n1,10\n2,\n";
+        let rows = arrow_struct::csv::read::<Positional>(csv.as_bytes())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            [
+                Positional {
+                    id: 1,
+                    value: Some(10)
+                },
+                Positional { id: 2, value: None }
+            ]
+        );
+
+        let csv = "id,value\n1,10\n2,\n";
+        let rows = arrow_struct::csv::read_inferred::<DefaultOnNull>(Cursor::new(csv))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            [
+                DefaultOnNull { id: 1, value: 10 },
+                DefaultOnNull { id: 2, value: 0 }
+            ]
+        );
+
+        let error = arrow_struct::csv::read::<Account>("nonce,address\nx,\n".as_bytes())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert!(error.to_string().contains("Error while parsing value x"));
+        let error = arrow_struct::csv::read_inferred::<DefaultOnNull>(Cursor::new("id\n1\n"))
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "missing columns: value");
+    }
+
+    #[test]
+    fn json() {
+        let json = r#"{"hash": "0x1", "block_number": 1, "topics": [1, 2], "sender": {"nonce": 3}}
+{"hash": "0x2", "block_number": 2, "amount": 100, "extra": true}
+"#;
+        let rows = arrow_struct::json::read::<Transfer>(json.as_bytes())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let expected = [
+            Transfer {
+                hash: "0x1".to_string(),
+                block_number: 1,
+                amount: None,
+                topics: Some(vec![1, 2]),
+                sender: Some(Account {
+                    address: None,
+                    nonce: Some(3),
+                }),
+            },
+            Transfer {
+                hash: "0x2".to_string(),
+                block_number: 2,
+                amount: Some(100),
+                topics: None,
+                sender: None,
+            },
+        ];
+        assert_eq!(rows, expected);
+
+        let json = r#"{"n": 1, "addr": "0x1", "amount": "12"}"#;
+        let rows = arrow_struct::json::read::<Renamed>(json.as_bytes())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            [Renamed {
+                address: Some("0x1".to_string()),
+                nonce: Some(1),
+                amount: Some(12),
+            }]
+        );
+        // Keys are in the order they first appear
+        let json = "{\"id\": 1, \"value\": 10}\n{\"id\": 2}\n";
+        let rows = arrow_struct::json::read::<Positional>(json.as_bytes())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            [
+                Positional {
+                    id: 1,
+                    value: Some(10)
+                },
+                Positional { id: 2, value: None }
+            ]
+        );
+
+        let json = "{\"id\": 1, \"value\": 10}\n{\"id\": 2}\n";
+        let rows = arrow_struct::json::read_inferred::<DefaultOnNull>(Cursor::new(json))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            [
+                DefaultOnNull { id: 1, value: 10 },
+                DefaultOnNull { id: 2, value: 0 }
+            ]
+        );
+
+        // `hash` is null
+        let error = arrow_struct::json::read::<Transfer>(r#"{"block_number": 1}"#.as_bytes())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(error.to_string(), "column hash: unexpected null at row 0");
+    }
+
+    #[test]
+    fn stream() {
+        use arrow::error::ArrowError;
+        use futures::{StreamExt, TryStreamExt};
+
+        let batch = RecordBatch::from(nullable_ints().as_struct());
+        let expected = vec![
+            DefaultOnNull { id: 1, value: 10 },
+            DefaultOnNull { id: 2, value: 0 },
+            DefaultOnNull { id: 3, value: 30 },
+        ];
+        let batches =
+            || futures::stream::iter([Ok::<_, ArrowError>(batch.clone()), Ok(batch.clone())]);
+
+        let rows = arrow_struct::stream::rows::<DefaultOnNull, _>(batches());
+        let rows = futures::executor::block_on(rows.try_collect::<Vec<_>>()).unwrap();
+        assert_eq!(rows.len(), 6);
+        assert_eq!(rows[..3], expected);
+        assert_eq!(rows[3..], expected);
+
+        let rows = arrow_struct::stream::batches::<DefaultOnNull, _>(batches());
+        let rows = futures::executor::block_on(rows.try_collect::<Vec<_>>()).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert!(rows.iter().all(|rows| *rows == expected));
+
+        // Errors of the stream and of every batch are passed on
+        let batches = futures::stream::iter([
+            Err(ArrowError::ComputeError("failed".to_string())),
+            Ok(batch),
+        ]);
+        let rows = arrow_struct::stream::rows::<Transfer, _>(batches);
+        let errors = futures::executor::block_on(
+            rows.map(|row| row.unwrap_err().to_string())
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(
+            errors,
+            [
+                "Compute error: failed",
+                "missing columns: hash, block_number, amount, topics, sender"
+            ]
+        );
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct ListField {
+        values: Option<Vec<i64>>,
+    }
+
+    /// The iterator owns the array, so it can be returned
+    fn owned_values() -> impl Iterator<Item = Option<i64>> + Send {
+        let array = Int64Array::from_iter((0..2_600).map(|i| (i % 3 != 0).then_some(i)));
+        // Starts at row 100
+        Option::<i64>::from_array_owned(Array::slice(&array, 100, 2_500))
+    }
+
+    #[test]
+    fn from_array_owned() {
+        let values = std::thread::spawn(|| owned_values().collect::<Vec<_>>())
+            .join()
+            .unwrap();
+        assert_eq!(
+            values,
+            (100..2_600)
+                .map(|i| (i % 3 != 0).then_some(i))
+                .collect::<Vec<_>>()
+        );
+
+        let rows = DefaultOnNull::from_array_owned(nullable_ints()).collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            [
+                DefaultOnNull { id: 1, value: 10 },
+                DefaultOnNull { id: 2, value: 0 },
+                DefaultOnNull { id: 3, value: 30 },
+            ]
+        );
+
+        // Lists in every chunk start at their own values
+        let lists = ListArray::from_iter_primitive::<Int64Type, _, _>(
+            (0..2_000).map(|i| (i % 7 != 0).then(|| vec![Some(i), Some(i + 1)])),
+        );
+        let expected = (0..2_000)
+            .map(|i| (i % 7 != 0).then(|| vec![i, i + 1]))
+            .collect::<Vec<_>>();
+        let lists: ArrayRef = Arc::new(lists);
+        let values = Option::<Vec<i64>>::from_array_owned(lists.clone()).collect::<Vec<_>>();
+        assert_eq!(values, expected);
+        let values = Option::<Vec<i64>>::from_array_ref(&lists.slice(1_500, 3)).collect::<Vec<_>>();
+        assert_eq!(values, expected[1_500..1_503]);
+
+        let structs: ArrayRef = Arc::new(StructArray::from(vec![(
+            Arc::new(Field::new("values", lists.data_type().clone(), true)),
+            lists,
+        )]));
+        let rows = ListField::from_array_owned(structs)
+            .map(|row| row.values)
+            .collect::<Vec<_>>();
+        assert_eq!(rows, expected);
+    }
+
+    /// Reads the array on another thread, which needs the iterators to be `Send`
+    fn count_on_thread<'a, T: FromArrayRef<'a>>(array: &'a ArrayRef) -> usize {
+        let rows = T::from_array_ref(array);
+        let try_rows = T::try_from_array_ref(array).unwrap();
+        let coerced = T::coerce_from_array_ref(array).unwrap();
+        std::thread::scope(|scope| {
+            scope
+                .spawn(move || {
+                    let count = rows.count();
+                    assert_eq!(try_rows.count(), count);
+                    assert_eq!(coerced.count(), count);
+                    count
+                })
+                .join()
+                .unwrap()
+        })
+    }
+
+    #[test]
+    fn send_iterators() {
+        use arrow_struct::ArrowField;
+
+        let ints: ArrayRef = Arc::new(Int64Array::from(vec![1, 2, 3]));
+        macro_rules! assert_send {
+            ($($ty:ty),*) => {$(
+                let array = arrow::compute::cast(&ints, &<$ty>::data_type()).unwrap();
+                assert_eq!(count_on_thread::<$ty>(&array), 3);
+                assert_eq!(count_on_thread::<Option<$ty>>(&array), 3);
+            )*};
+        }
+        assert_send!(i8, i16, i32, i64, u8, u16, u32, u64, f32, f64, i128);
+        let bools = arrow::compute::cast(&ints, &DataType::Boolean).unwrap();
+        assert_eq!(count_on_thread::<Option<bool>>(&bools), 3);
+
+        let strings = arrow::compute::cast(&ints, &DataType::Utf8).unwrap();
+        assert_eq!(count_on_thread::<Option<String>>(&strings), 3);
+        assert_eq!(count_on_thread::<Option<&str>>(&strings), 3);
+        let binary = arrow::compute::cast(&ints, &DataType::Binary).unwrap();
+        assert_eq!(count_on_thread::<Option<bytes::Bytes>>(&binary), 3);
+        assert_eq!(count_on_thread::<Option<&[u8]>>(&binary), 3);
+        let lists: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+            Some(vec![Some(1)]),
+            None,
+            Some(vec![]),
+        ]));
+        assert_eq!(count_on_thread::<Option<Vec<i64>>>(&lists), 3);
+        assert_eq!(count_on_thread::<Option<Vec<Option<i64>>>>(&lists), 3);
+        let structs = nullable_ints();
+        assert_eq!(count_on_thread::<DefaultOnNull>(&structs), 3);
+        assert_eq!(count_on_thread::<Option<DefaultOnNull>>(&structs), 3);
+        // A field read by a custom decoder
+        let hashes: ArrayRef = Arc::new(StructArray::from(vec![(
+            Arc::new(Field::new("hash", DataType::Utf8, true)),
+            Arc::new(StringArray::from(vec![Some("0x01"), None, Some("0x02")])) as ArrayRef,
+        )]));
+        assert_eq!(count_on_thread::<Hex>(&hashes), 3);
+
+        // Owned iterators can be moved to threads that outlive the array's owner
+        fn count_owned<T: FromArrayOwned>(array: &ArrayRef) -> usize {
+            let rows = T::from_array_owned(array.clone());
+            std::thread::spawn(move || rows.count()).join().unwrap()
+        }
+        assert_eq!(count_owned::<Option<String>>(&strings), 3);
+        assert_eq!(count_owned::<Option<bytes::Bytes>>(&binary), 3);
+        assert_eq!(count_owned::<Option<Vec<i64>>>(&lists), 3);
+        assert_eq!(count_owned::<DefaultOnNull>(&structs), 3);
+        assert_eq!(count_owned::<Hex>(&hashes), 3);
+    }
+
+    /// Like a crate re-exporting arrow_struct
+    mod facade {
+        pub use arrow_struct::*;
+    }
+
+    mod crate_path {
+        use super::facade::{Deserialize, FromArrayRef};
+
+        // Generated code must not refer to the `arrow_struct` crate, which this shadows
+        #[allow(dead_code)]
+        mod arrow_struct {}
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        #[arrow_struct(crate = "super::facade")]
+        struct Facade {
+            id: i64,
+            #[arrow_struct(default)]
+            missing: Option<i64>,
+        }
+
+        #[test]
+        fn crate_path() {
+            let array = super::nullable_ints();
+            assert_eq!(
+                Facade::from_array_ref(&array).collect::<Vec<_>>(),
+                (1..=3)
+                    .map(|id| Facade { id, missing: None })
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
 }
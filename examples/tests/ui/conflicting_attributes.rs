@@ -0,0 +1,38 @@
+use arrow_struct::Deserialize;
+
+#[derive(Deserialize)]
+struct Inner {
+    id: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct Flatten {
+    #[arrow_struct(default, flatten)]
+    inner: Inner,
+}
+
+#[derive(Deserialize)]
+struct Skip {
+    #[arrow_struct(skip, alias = "id")]
+    id: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct PathAndAlias {
+    #[arrow_struct(path = "a.b", alias = "c")]
+    id: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct CoerceAndCast {
+    #[arrow_struct(coerce, cast)]
+    id: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct OnNullOption {
+    #[arrow_struct(on_null = "default")]
+    id: Option<i64>,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use arrow_struct::Deserialize;
+
+#[derive(Deserialize)]
+struct Struct {
+    #[arrow_struct(on_null = "default")]
+    #[arrow_struct(on_null = "skip")]
+    id: i64,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use arrow_struct::Deserialize;
+
+#[derive(Deserialize)]
+enum Enum {
+    A,
+    B,
+}
+
+fn main() {}
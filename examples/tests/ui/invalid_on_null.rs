@@ -0,0 +1,9 @@
+use arrow_struct::Deserialize;
+
+#[derive(Deserialize)]
+struct Struct {
+    #[arrow_struct(on_null = "ignore")]
+    id: i64,
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use arrow_struct::Deserialize;
+
+#[derive(Deserialize)]
+struct Tuple(Option<i32>, Option<i64>);
+
+fn main() {}
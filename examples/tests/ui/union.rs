@@ -0,0 +1,9 @@
+use arrow_struct::Deserialize;
+
+#[derive(Deserialize)]
+union Union {
+    a: i32,
+    b: u32,
+}
+
+fn main() {}
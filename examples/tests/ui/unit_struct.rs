@@ -0,0 +1,6 @@
+use arrow_struct::Deserialize;
+
+#[derive(Deserialize)]
+struct Unit;
+
+fn main() {}
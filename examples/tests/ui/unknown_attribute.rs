@@ -0,0 +1,15 @@
+use arrow_struct::Deserialize;
+
+#[derive(Deserialize)]
+#[arrow_struct(rename_all = "camelCase")]
+struct Container {
+    id: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct Field {
+    #[arrow_struct(rename = "ID")]
+    id: Option<i64>,
+}
+
+fn main() {}